@@ -0,0 +1,589 @@
+use crate::controls::{Action, Bindings, Controls, local_gamepads};
+use crate::difficulty::Difficulty;
+use crate::player::GameMode;
+use crate::rng::GameRng;
+use crate::shield::ShieldSettings;
+use crate::states::{GameSets, GameStates};
+use bevy::app::{App, Last, Plugin, Update};
+use bevy::input::ButtonInput;
+use bevy::input::gamepad::Gamepad;
+use bevy::log::{info, warn};
+use bevy::prelude::{
+    AppExit, Commands, Deref, DerefMut, Entity, EventReader, IntoSystemConfigs, KeyCode,
+    MouseButton, NextState, OnEnter, OnTransition, Query, Res, ResMut, Resource, Time,
+};
+use std::fs;
+use std::io;
+use std::mem;
+use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
+
+/// Seconds of inactivity on the menu before a demo starts playing.
+const ATTRACT_DELAY: f32 = 10.0;
+
+/// Players that can take part in one game.
+pub const MAX_PLAYERS: usize = 4;
+
+/// What a ship is asked to do this tick, independent of where the input came
+/// from (keyboard, gamepad or a recording).
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PlayerActions {
+    pub rotate_left: bool,
+    pub rotate_right: bool,
+    pub thrust: bool,
+    /// Fire was pressed since the last tick
+    pub fire: bool,
+    /// Fire is held down, for automatic weapons
+    pub fire_held: bool,
+    pub hyperspace: bool,
+    /// Held to keep the energy shield up
+    pub shield: bool,
+    /// Analog rotation from -127 (left) to 127 (right), used instead of the
+    /// rotate buttons when not 0
+    pub steer: i8,
+}
+
+/// The actions of every player in one tick, indexed by `PlayerId`.
+pub type TickInputs = [PlayerActions; MAX_PLAYERS];
+
+/// What every ship is asked to do this tick.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PlayerInputs(pub TickInputs);
+
+impl PlayerActions {
+    pub fn from_input(
+        controls: &Controls,
+        keys: &ButtonInput<KeyCode>,
+        gamepad: Option<&Gamepad>,
+    ) -> Self {
+        let pressed = |action| controls.pressed(action, keys, gamepad);
+        let just_pressed = |action| controls.just_pressed(action, keys, gamepad);
+        let stick = gamepad.map_or(0.0, |g| g.left_stick().x);
+        Self {
+            rotate_left: pressed(Action::RotateLeft),
+            rotate_right: pressed(Action::RotateRight),
+            thrust: pressed(Action::Thrust),
+            fire: just_pressed(Action::Fire),
+            fire_held: pressed(Action::Fire),
+            hyperspace: just_pressed(Action::Hyperspace),
+            shield: pressed(Action::Shield),
+            steer: (stick.clamp(-1.0, 1.0) * 127.0).round() as i8,
+        }
+    }
+
+    /// The buttons in the low byte, the steering in the high one.
+    pub fn to_bits(self) -> u16 {
+        self.rotate_left as u16
+            | (self.rotate_right as u16) << 1
+            | (self.thrust as u16) << 2
+            | (self.fire as u16) << 3
+            | (self.hyperspace as u16) << 4
+            | (self.fire_held as u16) << 5
+            | (self.shield as u16) << 6
+            | u16::from(self.steer as u8) << 8
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        Self {
+            rotate_left: bits & 1 != 0,
+            rotate_right: bits & 1 << 1 != 0,
+            thrust: bits & 1 << 2 != 0,
+            fire: bits & 1 << 3 != 0,
+            hyperspace: bits & 1 << 4 != 0,
+            fire_held: bits & 1 << 5 != 0,
+            shield: bits & 1 << 6 != 0,
+            steer: (bits >> 8) as u8 as i8,
+        }
+    }
+}
+
+/// A recorded play session: the seed, difficulty, mode and shield setting the
+/// game was started with and the actions of every fixed tick. Stored as text,
+/// `seed <n>` and optional `difficulty <name>`, `mode <key>` and `shield on|off`
+/// lines, followed by a line per tick with a hexadecimal action mask for each
+/// player.
+pub struct Recording {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub shield: bool,
+    pub frames: Vec<TickInputs>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let content = fs::read_to_string(path)?;
+        let mut lines = content.lines().peekable();
+        let seed = lines
+            .next()
+            .and_then(|l| l.strip_prefix("seed "))
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| invalid("missing seed header"))?;
+        // Older recordings were all single player games on Normal, without
+        // the shield
+        let mut difficulty = Difficulty::Normal;
+        let mut mode = GameMode::Single;
+        let mut shield = false;
+        // Frames are hex and can start with a letter too, so only known
+        // headers are taken
+        while let Some(line) = lines.peek() {
+            if let Some(name) = line.strip_prefix("difficulty ") {
+                difficulty = Difficulty::from_name(name.trim())
+                    .ok_or_else(|| invalid("unknown difficulty"))?;
+            } else if let Some(key) = line.strip_prefix("mode ") {
+                mode = GameMode::from_key(key.trim()).ok_or_else(|| invalid("unknown mode"))?;
+            } else if let Some(state) = line.strip_prefix("shield ") {
+                shield = match state.trim() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(invalid("unknown shield setting")),
+                };
+            } else {
+                break;
+            }
+            lines.next();
+        }
+        let frames = lines
+            .map(|l| {
+                let mut frame = TickInputs::default();
+                for (actions, mask) in frame.iter_mut().zip(l.split_whitespace()) {
+                    *actions = PlayerActions::from_bits(u16::from_str_radix(mask, 16)?);
+                }
+                Ok(frame)
+            })
+            .collect::<Result<_, ParseIntError>>()
+            .map_err(|_| invalid("malformed frame"))?;
+        Ok(Self {
+            seed,
+            difficulty,
+            mode,
+            shield,
+            frames,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = format!(
+            "seed {}\ndifficulty {}\nmode {}\nshield {}\n",
+            self.seed,
+            self.difficulty.name(),
+            self.mode.key(),
+            if self.shield { "on" } else { "off" }
+        );
+        for frame in &self.frames {
+            let masks: Vec<String> = frame[..self.mode.players()]
+                .iter()
+                .map(|actions| format!("{:x}", actions.to_bits()))
+                .collect();
+            content.push_str(&masks.join(" "));
+            content.push('\n');
+        }
+        fs::write(path, content)
+    }
+}
+
+/// Records the actions of every played tick and writes them to `path` when
+/// the game ends.
+#[derive(Resource)]
+pub struct InputRecorder {
+    path: PathBuf,
+    frames: Vec<TickInputs>,
+}
+
+impl InputRecorder {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            frames: vec![],
+        }
+    }
+}
+
+/// Feeds a recording into `PlayerInputs` instead of the keyboard.
+#[derive(Resource)]
+pub struct InputPlayback {
+    difficulty: Difficulty,
+    mode: GameMode,
+    /// The shield setting of the recording, swapped with the one in use when
+    /// the game starts
+    shield: bool,
+    frames: Vec<TickInputs>,
+    cursor: usize,
+    /// Started from the menu as a demo. Any key returns to the menu and the
+    /// random source in use before the demo is restored afterwards.
+    attract: Option<GameRng>,
+}
+
+impl InputPlayback {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            difficulty: recording.difficulty,
+            mode: recording.mode,
+            shield: recording.shield,
+            frames: recording.frames,
+            cursor: 0,
+            attract: None,
+        }
+    }
+
+    pub fn is_attract(&self) -> bool {
+        self.attract.is_some()
+    }
+
+    fn next_frame(&mut self) -> Option<TickInputs> {
+        let frame = self.frames.get(self.cursor).copied();
+        self.cursor += 1;
+        frame
+    }
+}
+
+/// Plays the recording at `demo` after the menu has been idle for a while.
+#[derive(Resource)]
+pub struct AttractMode {
+    pub demo: PathBuf,
+    idle_time: f32,
+    /// The demo couldn't be loaded, so there is none to play
+    disabled: bool,
+}
+
+impl AttractMode {
+    pub fn new(demo: PathBuf) -> Self {
+        Self {
+            demo,
+            idle_time: 0.0,
+            disabled: false,
+        }
+    }
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self::new(PathBuf::from("assets/demo.input"))
+    }
+}
+
+/// Keyboard and gamepad state sampled every frame and handed to the simulation
+/// on its next tick. Presses are latched so a tap between two ticks isn't lost.
+#[derive(Resource, Default)]
+pub struct LocalActions(TickInputs);
+
+pub fn gather_local_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<(Entity, &Gamepad)>,
+    bindings: Res<Bindings>,
+    mut local: ResMut<LocalActions>,
+) {
+    let gamepads = local_gamepads(&gamepads);
+    for (i, (actions, controls)) in local.0.iter_mut().zip(&bindings.players).enumerate() {
+        // Presses are kept until a fixed tick has seen them
+        let PlayerActions {
+            fire, hyperspace, ..
+        } = *actions;
+        *actions = PlayerActions::from_input(controls, &keys, gamepads.get(i).copied());
+        actions.fire |= fire;
+        actions.hyperspace |= hyperspace;
+    }
+}
+
+pub fn read_player_input(
+    mut commands: Commands,
+    mut local: ResMut<LocalActions>,
+    mut inputs: ResMut<PlayerInputs>,
+    playback: Option<ResMut<InputPlayback>>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    let Some(mut playback) = playback else {
+        **inputs = local.0;
+        for actions in &mut local.0 {
+            actions.fire = false;
+            actions.hyperspace = false;
+        }
+        return;
+    };
+
+    match playback.next_frame() {
+        Some(frame) => **inputs = frame,
+        None => {
+            **inputs = TickInputs::default();
+            if playback.attract.is_some() {
+                next_state.set(GameStates::Menu);
+            } else {
+                info!("Replay finished, handing control to the keyboard");
+                commands.remove_resource::<InputPlayback>();
+            }
+        }
+    }
+}
+
+pub fn record_player_input(
+    inputs: Res<PlayerInputs>,
+    playback: Option<Res<InputPlayback>>,
+    recorder: Option<ResMut<InputRecorder>>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    if playback.is_some_and(|p| p.attract.is_some()) {
+        return;
+    }
+    recorder.frames.push(**inputs);
+}
+
+/// A replay is played on the difficulty, in the mode and with the shield
+/// setting it was recorded with.
+pub fn apply_playback(
+    playback: Option<ResMut<InputPlayback>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<GameMode>,
+    mut shield: ResMut<ShieldSettings>,
+) {
+    if let Some(mut playback) = playback {
+        *difficulty = playback.difficulty;
+        *mode = playback.mode;
+        mem::swap(&mut shield.enabled, &mut playback.shield);
+    }
+}
+
+fn start_recording(recorder: Option<ResMut<InputRecorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.frames.clear();
+    }
+}
+
+fn save_recording(
+    recorder: &InputRecorder,
+    rng: &GameRng,
+    difficulty: Difficulty,
+    mode: GameMode,
+    shield: &ShieldSettings,
+) {
+    if recorder.frames.is_empty() {
+        return;
+    }
+    let recording = Recording {
+        seed: rng.seed(),
+        difficulty,
+        mode,
+        shield: shield.enabled,
+        frames: recorder.frames.clone(),
+    };
+    match recording.save(&recorder.path) {
+        Ok(()) => info!(
+            "Recorded {} frames to {}",
+            recording.frames.len(),
+            recorder.path.display()
+        ),
+        Err(e) => warn!("Failed to save recording {}: {e}", recorder.path.display()),
+    }
+}
+
+fn finish_session(
+    mut commands: Commands,
+    rng: Res<GameRng>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    shield: Res<ShieldSettings>,
+    playback: Option<Res<InputPlayback>>,
+    recorder: Option<ResMut<InputRecorder>>,
+) {
+    if let Some(playback) = playback {
+        // A demo is cleaned up once it is back on the menu
+        if playback.is_attract() {
+            return;
+        }
+        commands.remove_resource::<InputPlayback>();
+    }
+    if let Some(mut recorder) = recorder {
+        save_recording(&recorder, &rng, *difficulty, *mode, &shield);
+        recorder.frames.clear();
+    }
+}
+
+fn save_on_exit(
+    exit: EventReader<AppExit>,
+    rng: Res<GameRng>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    shield: Res<ShieldSettings>,
+    recorder: Option<Res<InputRecorder>>,
+) {
+    if exit.is_empty() {
+        return;
+    }
+    if let Some(recorder) = recorder {
+        save_recording(&recorder, &rng, *difficulty, *mode, &shield);
+    }
+}
+
+fn end_attract_mode(
+    mut commands: Commands,
+    mut attract: ResMut<AttractMode>,
+    mut rng: ResMut<GameRng>,
+    mut shield: ResMut<ShieldSettings>,
+    playback: Option<ResMut<InputPlayback>>,
+) {
+    attract.idle_time = 0.0;
+    if let Some(mut playback) = playback
+        && let Some(previous) = playback.attract.take()
+    {
+        commands.remove_resource::<InputPlayback>();
+        *rng = previous;
+        // Swapped in when the demo started
+        shield.enabled = playback.shield;
+    }
+}
+
+/// Anything held on the keyboard, the mouse or a gamepad, or a stick that is
+/// pushed.
+fn any_input(
+    keys: &ButtonInput<KeyCode>,
+    mouse: &ButtonInput<MouseButton>,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    keys.get_pressed().next().is_some()
+        || mouse.get_pressed().next().is_some()
+        || gamepads.iter().any(|g| {
+            g.get_pressed().next().is_some()
+                || g.left_stick().length() > 0.5
+                || g.right_stick().length() > 0.5
+        })
+}
+
+/// Puts off the demo while someone is using the menu.
+pub fn reset_idle_time(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut attract: ResMut<AttractMode>,
+) {
+    if any_input(&keys, &mouse, &gamepads) {
+        attract.idle_time = 0.0;
+    }
+}
+
+pub fn start_attract_mode(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut attract: ResMut<AttractMode>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if attract.disabled {
+        return;
+    }
+    attract.idle_time += time.delta_secs();
+    if attract.idle_time < ATTRACT_DELAY {
+        return;
+    }
+    attract.idle_time = 0.0;
+
+    let recording = match Recording::load(&attract.demo) {
+        Ok(recording) => recording,
+        Err(e) => {
+            warn!(
+                "No demo to play from {}, attract mode is off: {e}",
+                attract.demo.display()
+            );
+            attract.disabled = true;
+            return;
+        }
+    };
+    let previous = mem::replace(&mut *rng, GameRng::new(recording.seed));
+    let mut playback = InputPlayback::new(recording);
+    playback.attract = Some(previous);
+    commands.insert_resource(playback);
+    next_state.set(GameStates::Playing);
+}
+
+pub fn stop_attract_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    playback: Option<Res<InputPlayback>>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if playback.is_none_or(|p| p.attract.is_none()) {
+        return;
+    }
+    let gamepad_pressed = gamepads
+        .iter()
+        .any(|g| g.get_just_pressed().next().is_some());
+    if keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || gamepad_pressed
+    {
+        next_state.set(GameStates::Menu);
+    }
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerInputs>()
+            .init_resource::<LocalActions>()
+            .init_resource::<AttractMode>()
+            .add_systems(Update, stop_attract_mode.in_set(GameSets::Playing))
+            .add_systems(
+                Update,
+                (reset_idle_time, start_attract_mode)
+                    .chain()
+                    .in_set(GameSets::Menu),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameStates::Menu,
+                    entered: GameStates::Playing,
+                },
+                start_recording,
+            )
+            .add_systems(OnEnter(GameStates::GameOver), finish_session)
+            .add_systems(
+                OnTransition {
+                    exited: GameStates::Paused,
+                    entered: GameStates::Menu,
+                },
+                finish_session,
+            )
+            .add_systems(OnEnter(GameStates::Menu), end_attract_mode)
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_starting_with_a_letter_are_not_headers() {
+        let path = std::env::temp_dir().join(format!("recording-{}.input", std::process::id()));
+        let mut first = TickInputs::default();
+        first[0] = PlayerActions {
+            rotate_right: true,
+            fire: true,
+            ..PlayerActions::default()
+        };
+        let mut second = TickInputs::default();
+        second[0].steer = -1;
+        let recording = Recording {
+            seed: 7,
+            difficulty: Difficulty::Hard,
+            mode: GameMode::Single,
+            shield: true,
+            frames: vec![first, second],
+        };
+
+        recording.save(&path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let loaded = Recording::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(content.ends_with("shield on\na\nff00\n"), "{content}");
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+        assert!(loaded.shield);
+        assert_eq!(loaded.frames, recording.frames);
+    }
+}
@@ -1,48 +1,129 @@
-use crate::helpers::random_range;
+use crate::collision::{AsteroidDestroyed, DestroyedBy};
+use crate::config::GameConfig;
+use crate::difficulty::DifficultySettings;
+use crate::ghosts::GhostRendered;
+use crate::helpers::{get_random_vel, random_range};
+use crate::lifetime::Lifetime;
+use crate::rng::GameRng;
+use crate::spatial::Collider;
 use crate::transforming::Physics;
 use bevy::color::Color;
 use bevy::math::Vec2;
-use bevy::prelude::{Commands, Component, Transform, default};
+use bevy::prelude::{Commands, Component, EventReader, Res, ResMut, Sprite, Transform, default};
 use bevy_prototype_lyon::draw::Stroke;
 use bevy_prototype_lyon::entity::ShapeBundle;
 use bevy_prototype_lyon::geometry::GeometryBuilder;
 use bevy_prototype_lyon::shapes;
+use fastrand::Rng;
 use std::f32::consts::PI;
 
 #[derive(Component)]
 pub struct Asteroid {
     pub size: f32,
+    /// Outline in local space, as drawn
+    pub points: Vec<Vec2>,
 }
 
-pub const MAX_SIZE: f32 = 60.0;
-
-pub fn create_asteroid(commands: &mut Commands, size: f32, position: Vec2, vel: Vec2) {
+pub fn create_asteroid(
+    commands: &mut Commands,
+    rng: &mut Rng,
+    size: f32,
+    position: Vec2,
+    vel: Vec2,
+) {
     let mut points = vec![];
     let mut angle = 0.0;
     while angle < PI * 2.0 {
-        let rand_size = random_range(size / 1.5..size);
+        let rand_size = random_range(rng, size / 1.5..size);
         points.push(Vec2::from_angle(angle) * rand_size);
-        angle += random_range(PI / 10.0..PI / 6.0);
+        angle += random_range(rng, PI / 10.0..PI / 6.0);
     }
 
-    let player_triangle = shapes::Polygon {
-        points,
+    let outline = shapes::Polygon {
+        points: points.clone(),
         closed: true,
     };
 
     commands.spawn((
         ShapeBundle {
-            path: GeometryBuilder::build_as(&player_triangle),
+            path: GeometryBuilder::build_as(&outline),
             transform: Transform::from_xyz(position.x, position.y, 0.0),
             ..default()
         },
         Stroke::new(Color::WHITE, 1.0),
         Physics {
             max_vel: Vec2::new(50.0, 50.0),
-            rot_vel: random_range(-1.0..1.0),
+            rot_vel: random_range(rng, -1.0..1.0),
             vel,
             ..default()
         },
-        Asteroid { size },
+        Asteroid { size, points },
+        Collider { radius: size },
+        GhostRendered { radius: size },
     ));
 }
+
+fn spawn_asteroids(
+    commands: &mut Commands,
+    rng: &mut GameRng,
+    config: &GameConfig,
+    count: i32,
+    pos: Vec2,
+    size: f32,
+) {
+    let modifier = config.asteroid_max_size / size;
+    for _ in 0..count {
+        let vel = get_random_vel(&mut rng.splits, 20.0 * modifier..100.0 * modifier);
+        create_asteroid(commands, &mut rng.asteroids, size, pos, vel);
+    }
+}
+
+pub fn split_asteroids(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    difficulty: Res<DifficultySettings>,
+    mut rng: ResMut<GameRng>,
+    mut destroyed: EventReader<AsteroidDestroyed>,
+) {
+    for event in destroyed.read() {
+        // Small asteroids are shot to pieces, but still break when rammed
+        if matches!(event.by, DestroyedBy::Bullet { .. }) && event.size < 20.0 {
+            continue;
+        }
+        spawn_asteroids(
+            &mut commands,
+            &mut rng,
+            &config,
+            difficulty.split_count,
+            event.position,
+            event.size / 2.0,
+        );
+    }
+}
+
+pub fn spawn_explosions(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut destroyed: EventReader<AsteroidDestroyed>,
+) {
+    for event in destroyed.read() {
+        if let DestroyedBy::Bullet { .. } = event.by {
+            spawn_explosion(&mut commands, &mut rng.particles, event.position);
+        }
+    }
+}
+
+pub fn spawn_explosion(commands: &mut Commands, rng: &mut Rng, position: Vec2) {
+    let particles = rng.i32(4..10);
+
+    for _ in 0..particles {
+        let rand_dir = get_random_vel(rng, 30.0..100.0);
+        let size = random_range(rng, 2.0..3.0);
+        commands.spawn((
+            Transform::from_xyz(position.x, position.y, 0.0),
+            Physics::new_with_vel(rand_dir),
+            Lifetime::new(0.8),
+            Sprite::from_color(Color::WHITE, Vec2::new(size, size)),
+        ));
+    }
+}
@@ -1,4 +1,32 @@
-use bevy::prelude::Component;
+use bevy::prelude::{Component, Entity, Resource};
 
-#[derive(Component)]
-pub struct Bullet;
+/// Which side a bullet was fired for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Team {
+    Players,
+    Saucers,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Bullet {
+    /// Whoever fired it. It may have been destroyed since.
+    pub owner: Entity,
+    pub team: Team,
+}
+
+/// Who bullets may hit besides asteroids. A ship is never hit by its own
+/// bullets and saucers never shoot each other.
+#[derive(Resource, Default)]
+pub struct BulletRules {
+    /// Players' bullets hit other players
+    pub friendly_fire: bool,
+}
+
+impl BulletRules {
+    pub fn hits_ship(&self, bullet: &Bullet, ship: Entity) -> bool {
+        match bullet.team {
+            Team::Saucers => true,
+            Team::Players => self.friendly_fire && bullet.owner != ship,
+        }
+    }
+}
@@ -1,103 +1,629 @@
-use crate::asteroid::{Asteroid, create_asteroid};
-use crate::bullet::Bullet;
-use crate::helpers::{get_random_vel, random_range};
-use crate::lifetime::Lifetime;
-use crate::player::{Player, ShipInvincibility};
-use crate::spawner::Spawner;
-use crate::states::{GameStates, PLAYER_SIZE};
-use crate::transforming::Physics;
-use crate::ui::PlayerLives;
-use crate::{Score, asteroid};
-use bevy::math::{Quat, Vec2, Vec3};
-use bevy::prelude::{Commands, Entity, NextState, Query, ResMut, Transform, With, Without};
-use bevy::sprite::Sprite;
-use bevy_color::Color;
-
-fn spawn_asteroids(commands: &mut Commands, count: i32, pos: Vec2, size: f32) {
-    let modifier = asteroid::MAX_SIZE / size;
-    for _ in 0..count {
-        create_asteroid(
-            commands,
-            size,
-            pos,
-            get_random_vel(20.0 * modifier..100.0 * (asteroid::MAX_SIZE / size)),
-        );
+use crate::asteroid::Asteroid;
+use crate::bullet::{Bullet, BulletRules, Team};
+use crate::player::{Hyperspace, Player, ShipInvincibility, ShipSize};
+use crate::polygon::{contains_point, convex_overlap, star_overlap, to_world};
+use crate::saucer::{Saucer, SaucerKind};
+use crate::shield::{shield_outline, shield_radius};
+use crate::spatial::SpatialGrid;
+use crate::transforming::{Playfield, SimId};
+use bevy::ecs::entity::EntityHashSet;
+use bevy::math::{Vec2, Vec3};
+use bevy::prelude::{
+    Commands, Entity, Event, EventReader, EventWriter, Query, Res, ResMut, Resource, Transform,
+    With, Without,
+};
+
+/// What destroyed an asteroid, a saucer or the ship.
+// Not every field is read by the game itself, they are there for listeners
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub enum DestroyedBy {
+    Bullet {
+        bullet: Entity,
+        owner: Entity,
+        team: Team,
+    },
+    Ship(Entity),
+    Saucer(Entity),
+    Asteroid(Entity),
+}
+
+impl DestroyedBy {
+    pub fn bullet(entity: Entity, bullet: &Bullet) -> Self {
+        DestroyedBy::Bullet {
+            bullet: entity,
+            owner: bullet.owner,
+            team: bullet.team,
+        }
     }
+
+    /// The player ship credited with the kill, if it was a player's doing.
+    pub fn player(&self) -> Option<Entity> {
+        match *self {
+            DestroyedBy::Bullet {
+                owner,
+                team: Team::Players,
+                ..
+            } => Some(owner),
+            DestroyedBy::Ship(ship) => Some(ship),
+            _ => None,
+        }
+    }
+}
+
+/// Sent when an asteroid has been hit. Listeners take care of the outcome
+/// (despawning, scoring, splitting, effects), collision code only detects it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AsteroidDestroyed {
+    pub asteroid: Entity,
+    pub size: f32,
+    pub position: Vec2,
+    pub by: DestroyedBy,
 }
 
+/// Sent when a saucer has been shot, rammed or has crashed into an asteroid.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SaucerDestroyed {
+    pub saucer: Entity,
+    pub kind: SaucerKind,
+    pub position: Vec2,
+    pub by: DestroyedBy,
+}
+
+/// Sent when the ship runs into an asteroid or a saucer, or is shot. `by` is
+/// `None` when the ship blew itself up jumping out of hyperspace.
+#[allow(dead_code)]
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShipHit {
+    pub ship: Entity,
+    pub by: Option<DestroyedBy>,
+}
+
+/// Sent when an asteroid runs into a raised shield.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ShieldHit {
+    pub ship: Entity,
+    pub asteroid: Entity,
+}
+
+/// Entities used up by a collision during the current tick. Every check skips
+/// them, so nothing is destroyed, scored or split twice.
+#[derive(Resource, Default)]
+pub struct ConsumedThisTick(EntityHashSet);
+
+pub fn clear_consumed(mut consumed: ResMut<ConsumedThisTick>) {
+    consumed.0.clear();
+}
+
+/// Pairs every bullet with at most one target and every target, an asteroid or
+/// a ship, with at most one bullet. Candidates are `(bullet, target)` in the
+/// order they should win; anything already consumed is skipped and the winners
+/// are added.
+pub fn resolve_hits(
+    candidates: &[(Entity, Entity)],
+    consumed: &mut EntityHashSet,
+) -> Vec<(Entity, Entity)> {
+    let mut hits = vec![];
+    for &(bullet, target) in candidates {
+        if consumed.contains(&bullet) || consumed.contains(&target) {
+            continue;
+        }
+        consumed.insert(bullet);
+        consumed.insert(target);
+        hits.push((bullet, target));
+    }
+    hits
+}
+
+/// Transform of the copy of `target` that is closest to `from`, taking the
+/// wrapping edges into account.
+fn nearest_image(playfield: &Playfield, from: Vec3, target: &Transform) -> Transform {
+    let delta = playfield.wrapped_delta(from.truncate(), target.translation.truncate());
+    Transform {
+        translation: from + delta.extend(0.0),
+        ..*target
+    }
+}
+
+type CollidingShips<'a> = (
+    Entity,
+    &'a Transform,
+    &'a ShipInvincibility,
+    &'a Hyperspace,
+    &'a ShipSize,
+    &'a SimId,
+);
+
 pub fn check_bullet_collision(
-    mut score: ResMut<Score>,
-    mut commands: Commands,
-    bullets: Query<(Entity, &Transform), With<Bullet>>,
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    bullets: Query<(Entity, &Transform, &Bullet, &SimId)>,
     asteroids: Query<(Entity, &Transform, &Asteroid)>,
+    mut destroyed: EventWriter<AsteroidDestroyed>,
 ) {
-    for (be, bt) in &bullets {
-        for (ae, at, aa) in &asteroids {
-            let dist = bt.translation.distance(at.translation);
-            if dist < 4.0 || dist < aa.size {
-                **score += 1;
-                commands.entity(be).despawn();
-                commands.entity(ae).despawn();
-                spawn_explosion(&mut commands, at.translation);
-                if aa.size < 20.0 {
-                    continue;
-                }
-                let pos = Vec2::new(at.translation.x, at.translation.y);
-                spawn_asteroids(&mut commands, 2, pos, aa.size / 2.0);
+    let mut candidates = vec![];
+    for (be, bt, ..) in bullets.iter().sort::<&SimId>() {
+        for candidate in grid.query(bt.translation.truncate(), 0.0) {
+            let Ok((ae, at, aa)) = asteroids.get(candidate) else {
+                continue;
+            };
+            let image = nearest_image(&playfield, bt.translation, at);
+            let outline = to_world(&aa.points, &image);
+            if contains_point(&outline, bt.translation.truncate()) {
+                candidates.push((be, ae));
             }
         }
     }
+
+    for (be, ae) in resolve_hits(&candidates, &mut consumed.0) {
+        let (_, at, aa) = asteroids.get(ae).expect("hit asteroid was just queried");
+        let (_, _, bullet, _) = bullets.get(be).expect("bullet was just queried");
+        destroyed.send(AsteroidDestroyed {
+            asteroid: ae,
+            size: aa.size,
+            position: at.translation.truncate(),
+            by: DestroyedBy::bullet(be, bullet),
+        });
+    }
 }
+
+/// Runs before `check_player_collision`, which ignores shielded ships.
+pub fn check_shield_collision(
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    consumed: Res<ConsumedThisTick>,
+    players: Query<CollidingShips, With<Player>>,
+    asteroids: Query<(Entity, &Transform, &Asteroid), Without<Player>>,
+    mut shield_hit: EventWriter<ShieldHit>,
+) {
+    for (pe, pt, si, hyperspace, size, _) in players.iter().sort::<&SimId>() {
+        if !si.is_shielded() || hyperspace.is_jumping() {
+            continue;
+        }
+        let radius = shield_radius(*size);
+        let shield = shield_outline(pt.translation.truncate(), radius);
+        for candidate in grid.query(pt.translation.truncate(), radius) {
+            let Ok((ae, at, aa)) = asteroids.get(candidate) else {
+                continue;
+            };
+            if consumed.0.contains(&ae) {
+                continue;
+            }
+            let image = nearest_image(&playfield, pt.translation, at);
+            if pt.translation.distance(image.translation) > aa.size + radius {
+                continue;
+            }
+            let outline = to_world(&aa.points, &image);
+            if star_overlap(image.translation.truncate(), &outline, &shield) {
+                shield_hit.send(ShieldHit {
+                    ship: pe,
+                    asteroid: ae,
+                });
+            }
+        }
+    }
+}
+
 pub fn check_player_collision(
-    mut commands: Commands,
-    mut players: Query<
-        (Entity, &mut Transform, &mut Physics, &mut ShipInvincibility),
-        With<Player>,
-    >,
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    players: Query<CollidingShips, With<Player>>,
     asteroids: Query<(Entity, &Transform, &Asteroid), Without<Player>>,
-    mut spawner: Query<&mut Spawner>,
-    mut lives: ResMut<PlayerLives>,
-    mut next_state: ResMut<NextState<GameStates>>,
+    mut destroyed: EventWriter<AsteroidDestroyed>,
+    mut ship_hit: EventWriter<ShipHit>,
+) {
+    for (pe, pt, si, hyperspace, size, _) in players.iter().sort::<&SimId>() {
+        if si.is_invincible() || hyperspace.is_jumping() {
+            continue;
+        }
+        // The nose of the ship is the furthest point from its centre
+        let ship_radius = size.0;
+        let ship = to_world(&size.outline(), pt);
+        for candidate in grid.query(pt.translation.truncate(), ship_radius) {
+            let Ok((ae, at, aa)) = asteroids.get(candidate) else {
+                continue;
+            };
+            if consumed.0.contains(&ae) {
+                continue;
+            }
+            let image = nearest_image(&playfield, pt.translation, at);
+            // Cheap bounding circle check before the exact test
+            if pt.translation.distance(image.translation) > aa.size + ship_radius {
+                continue;
+            }
+            let outline = to_world(&aa.points, &image);
+            if star_overlap(image.translation.truncate(), &outline, &ship) {
+                consumed.0.insert(ae);
+                destroyed.send(AsteroidDestroyed {
+                    asteroid: ae,
+                    size: aa.size,
+                    position: at.translation.truncate(),
+                    by: DestroyedBy::Ship(pe),
+                });
+                ship_hit.send(ShipHit {
+                    ship: pe,
+                    by: Some(DestroyedBy::Asteroid(ae)),
+                });
+                // The ship is sent back to the centre, it can't hit anything else
+                break;
+            }
+        }
+    }
+}
+
+pub fn check_bullet_saucer_collision(
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    bullets: Query<(Entity, &Transform, &Bullet, &SimId)>,
+    saucers: Query<(Entity, &Transform, &Saucer)>,
+    mut destroyed: EventWriter<SaucerDestroyed>,
+) {
+    let mut candidates = vec![];
+    for (be, bt, bullet, _) in bullets.iter().sort::<&SimId>() {
+        if bullet.team == Team::Saucers {
+            continue;
+        }
+        for candidate in grid.query(bt.translation.truncate(), 0.0) {
+            let Ok((se, st, saucer)) = saucers.get(candidate) else {
+                continue;
+            };
+            let image = nearest_image(&playfield, bt.translation, st);
+            let hull = to_world(&saucer.points, &image);
+            if contains_point(&hull, bt.translation.truncate()) {
+                candidates.push((be, se));
+            }
+        }
+    }
+
+    for (be, se) in resolve_hits(&candidates, &mut consumed.0) {
+        let (_, st, saucer) = saucers.get(se).expect("hit saucer was just queried");
+        let (_, _, bullet, _) = bullets.get(be).expect("bullet was just queried");
+        destroyed.send(SaucerDestroyed {
+            saucer: se,
+            kind: saucer.kind,
+            position: st.translation.truncate(),
+            by: DestroyedBy::bullet(be, bullet),
+        });
+    }
+}
+
+/// Saucers don't steer around asteroids, both break up when they meet.
+pub fn check_saucer_asteroid_collision(
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    saucers: Query<(Entity, &Transform, &Saucer, &SimId)>,
+    asteroids: Query<(Entity, &Transform, &Asteroid)>,
+    mut destroyed: EventWriter<AsteroidDestroyed>,
+    mut saucer_destroyed: EventWriter<SaucerDestroyed>,
+) {
+    for (se, st, saucer, _) in saucers.iter().sort::<&SimId>() {
+        if consumed.0.contains(&se) {
+            continue;
+        }
+        let radius = saucer.kind.half_width();
+        let hull = to_world(&saucer.points, st);
+        for candidate in grid.query(st.translation.truncate(), radius) {
+            let Ok((ae, at, aa)) = asteroids.get(candidate) else {
+                continue;
+            };
+            if consumed.0.contains(&ae) {
+                continue;
+            }
+            let image = nearest_image(&playfield, st.translation, at);
+            if st.translation.distance(image.translation) > aa.size + radius {
+                continue;
+            }
+            let outline = to_world(&aa.points, &image);
+            if star_overlap(image.translation.truncate(), &outline, &hull) {
+                consumed.0.insert(se);
+                consumed.0.insert(ae);
+                destroyed.send(AsteroidDestroyed {
+                    asteroid: ae,
+                    size: aa.size,
+                    position: at.translation.truncate(),
+                    by: DestroyedBy::Saucer(se),
+                });
+                saucer_destroyed.send(SaucerDestroyed {
+                    saucer: se,
+                    kind: saucer.kind,
+                    position: st.translation.truncate(),
+                    by: DestroyedBy::Asteroid(ae),
+                });
+                break;
+            }
+        }
+    }
+}
+
+pub fn check_player_saucer_collision(
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    players: Query<CollidingShips, With<Player>>,
+    saucers: Query<(Entity, &Transform, &Saucer)>,
+    mut saucer_destroyed: EventWriter<SaucerDestroyed>,
+    mut ship_hit: EventWriter<ShipHit>,
 ) {
-    for (pe, mut pt, mut pp, mut si) in &mut players {
-        if si.is_invincible() {
-            return;
-        }
-        for (ae, at, aa) in &asteroids {
-            let dist_to_ast = pt.translation.distance(at.translation);
-            if dist_to_ast < aa.size || dist_to_ast < PLAYER_SIZE / 2.0 {
-                commands.entity(ae).despawn();
-                let pos = Vec2::new(at.translation.x, at.translation.y);
-                spawn_asteroids(&mut commands, 2, pos, aa.size / 2.0);
-
-                if let Ok(mut spawner) = spawner.get_single_mut() {
-                    spawner.reset();
-                }
-                **lives -= 1;
-                si.set_invincible();
-                pt.translation = Vec3::ZERO;
-                pt.rotation = Quat::from_rotation_z(0.0);
-                pp.reset();
-                if **lives == 0 {
-                    commands.entity(pe).despawn();
-                    next_state.set(GameStates::Menu);
-                }
-            }
-        }
-    }
-}
-
-fn spawn_explosion(commands: &mut Commands, position: Vec3) {
-    let particles = fastrand::i32(4..10);
-
-    for _ in 0..particles {
-        let rand_dir = get_random_vel(30.0..100.0);
-        let size = random_range(2.0..3.0);
-        commands.spawn((
-            Transform::from_translation(position),
-            Physics::new_with_vel(rand_dir),
-            Lifetime::new(0.8),
-            Sprite::from_color(Color::WHITE, Vec2::new(size, size)),
+    for (pe, pt, si, hyperspace, size, _) in players.iter().sort::<&SimId>() {
+        if si.is_invincible() || hyperspace.is_jumping() {
+            continue;
+        }
+        let ship = to_world(&size.outline(), pt);
+        for candidate in grid.query(pt.translation.truncate(), size.0) {
+            let Ok((se, st, saucer)) = saucers.get(candidate) else {
+                continue;
+            };
+            if consumed.0.contains(&se) {
+                continue;
+            }
+            let image = nearest_image(&playfield, pt.translation, st);
+            if pt.translation.distance(image.translation) > saucer.kind.half_width() + size.0 {
+                continue;
+            }
+            if convex_overlap(&to_world(&saucer.points, &image), &ship) {
+                consumed.0.insert(se);
+                saucer_destroyed.send(SaucerDestroyed {
+                    saucer: se,
+                    kind: saucer.kind,
+                    position: st.translation.truncate(),
+                    by: DestroyedBy::Ship(pe),
+                });
+                ship_hit.send(ShipHit {
+                    ship: pe,
+                    by: Some(DestroyedBy::Saucer(se)),
+                });
+                break;
+            }
+        }
+    }
+}
+
+/// Bullets from the other team, or from other players with friendly fire on.
+/// A ship is only hit by one bullet a tick, the others fly on.
+pub fn check_bullet_player_collision(
+    grid: Res<SpatialGrid>,
+    rules: Res<BulletRules>,
+    playfield: Res<Playfield>,
+    mut consumed: ResMut<ConsumedThisTick>,
+    players: Query<CollidingShips, With<Player>>,
+    bullets: Query<(Entity, &Transform, &Bullet, &SimId)>,
+    mut ship_hit: EventWriter<ShipHit>,
+) {
+    let mut candidates = vec![];
+    for (be, bt, bullet, _) in bullets.iter().sort::<&SimId>() {
+        for candidate in grid.query(bt.translation.truncate(), 0.0) {
+            let Ok((pe, pt, si, hyperspace, size, _)) = players.get(candidate) else {
+                continue;
+            };
+            if si.is_invincible() || hyperspace.is_jumping() || !rules.hits_ship(bullet, pe) {
+                continue;
+            }
+            // The copy of the ship closest to the bullet, across the edges
+            let image = nearest_image(&playfield, bt.translation, pt);
+            let ship = to_world(&size.outline(), &image);
+            if contains_point(&ship, bt.translation.truncate()) {
+                candidates.push((be, pe));
+            }
+        }
+    }
+
+    for (be, pe) in resolve_hits(&candidates, &mut consumed.0) {
+        let (_, _, bullet, _) = bullets.get(be).expect("bullet was just queried");
+        ship_hit.send(ShipHit {
+            ship: pe,
+            by: Some(DestroyedBy::bullet(be, bullet)),
+        });
+    }
+}
+
+pub fn despawn_destroyed(
+    mut commands: Commands,
+    mut destroyed: EventReader<AsteroidDestroyed>,
+    mut ship_hit: EventReader<ShipHit>,
+) {
+    for event in destroyed.read() {
+        commands.entity(event.asteroid).despawn();
+        if let DestroyedBy::Bullet { bullet, .. } = event.by {
+            commands.entity(bullet).despawn();
+        }
+    }
+    for event in ship_hit.read() {
+        if let Some(DestroyedBy::Bullet { bullet, .. }) = event.by {
+            commands.entity(bullet).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asteroid::split_asteroids;
+    use crate::config::GameConfig;
+    use crate::difficulty::DifficultySettings;
+    use crate::player::{Hyperspace, Player, ShipInvincibility, ShipSize};
+    use crate::rng::GameRng;
+    use crate::scoring::{ScoreTable, ScoringState, award_score};
+    use crate::spatial::{Collider, update_spatial_grid};
+    use crate::transforming::{Physics, SimIds};
+    use crate::ui::Score;
+    use bevy::app::{App, Update};
+    use bevy::prelude::{Events, IntoSystemConfigs};
+    use std::f32::consts::TAU;
+
+    const SIZE: f32 = 60.0;
+
+    fn entities<const N: usize>() -> [Entity; N] {
+        std::array::from_fn(|i| Entity::from_raw(i as u32))
+    }
+
+    #[test]
+    fn resolve_hits_pairs_each_bullet_and_asteroid_once() {
+        let [b1, b2, a1, a2] = entities();
+        let candidates = [(b1, a1), (b2, a1), (b1, a2), (b2, a2)];
+        let mut consumed = EntityHashSet::default();
+
+        let hits = resolve_hits(&candidates, &mut consumed);
+
+        assert_eq!(hits, vec![(b1, a1), (b2, a2)]);
+        assert_eq!(consumed.len(), 4);
+    }
+
+    #[test]
+    fn resolve_hits_skips_consumed_entities() {
+        let [b1, b2, a1, a2] = entities();
+        let mut consumed = EntityHashSet::from_iter([a1]);
+
+        let hits = resolve_hits(&[(b1, a1), (b1, a2), (b2, a2)], &mut consumed);
+
+        assert_eq!(hits, vec![(b1, a2)]);
+        assert!(!consumed.contains(&b2));
+    }
+
+    /// The collision check and the listeners that score and split.
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<SpatialGrid>()
+            .init_resource::<SimIds>()
+            .init_resource::<Playfield>()
+            .init_resource::<ConsumedThisTick>()
+            .init_resource::<GameConfig>()
+            .init_resource::<DifficultySettings>()
+            .insert_resource(GameRng::new(1))
+            .add_event::<AsteroidDestroyed>()
+            .add_event::<SaucerDestroyed>()
+            .add_event::<ShipHit>()
+            .add_systems(
+                Update,
+                (
+                    update_spatial_grid,
+                    clear_consumed,
+                    check_bullet_collision,
+                    despawn_destroyed,
+                    award_score,
+                    split_asteroids,
+                )
+                    .chain(),
+            );
+        app
+    }
+
+    fn spawn_ship(app: &mut App) -> Entity {
+        let state = ScoringState::new(&ScoreTable::default());
+        app.world_mut()
+            .spawn((Physics::new(), Score::default(), state))
+            .id()
+    }
+
+    fn spawn_asteroid(app: &mut App, position: Vec2) {
+        let points = (0..8)
+            .map(|i| Vec2::from_angle(i as f32 * TAU / 8.0) * SIZE)
+            .collect();
+        app.world_mut().spawn((
+            Transform::from_translation(position.extend(0.0)),
+            Physics::new(),
+            Asteroid { size: SIZE, points },
+            Collider { radius: SIZE },
+        ));
+    }
+
+    fn spawn_bullet(app: &mut App, owner: Entity, position: Vec2) {
+        app.world_mut().spawn((
+            Transform::from_translation(position.extend(0.0)),
+            Physics::new(),
+            Bullet {
+                owner,
+                team: Team::Players,
+            },
+        ));
+    }
+
+    fn count<T: bevy::prelude::Component>(app: &mut App) -> usize {
+        app.world_mut().query::<&T>().iter(app.world()).count()
+    }
+
+    fn score(app: &App, ship: Entity) -> usize {
+        **app.world().get::<Score>(ship).unwrap()
+    }
+
+    #[test]
+    fn two_bullets_in_one_asteroid_destroy_it_once() {
+        let mut app = app();
+        let ship = spawn_ship(&mut app);
+        spawn_asteroid(&mut app, Vec2::ZERO);
+        spawn_bullet(&mut app, ship, Vec2::new(-5.0, 0.0));
+        spawn_bullet(&mut app, ship, Vec2::new(5.0, 0.0));
+
+        app.update();
+
+        assert_eq!(score(&app, ship), ScoreTable::default().large);
+        // The asteroid is replaced by its fragments, the second bullet flies on
+        let split_count = DifficultySettings::default().split_count as usize;
+        assert_eq!(count::<Asteroid>(&mut app), split_count);
+        assert_eq!(count::<Bullet>(&mut app), 1);
+    }
+
+    #[test]
+    fn one_bullet_in_two_asteroids_destroys_only_one() {
+        let mut app = app();
+        let ship = spawn_ship(&mut app);
+        spawn_asteroid(&mut app, Vec2::new(-20.0, 0.0));
+        spawn_asteroid(&mut app, Vec2::new(20.0, 0.0));
+        spawn_bullet(&mut app, ship, Vec2::ZERO);
+
+        app.update();
+
+        assert_eq!(score(&app, ship), ScoreTable::default().large);
+        let split_count = DifficultySettings::default().split_count as usize;
+        assert_eq!(count::<Asteroid>(&mut app), 1 + split_count);
+        assert_eq!(count::<Bullet>(&mut app), 0);
+    }
+
+    #[test]
+    fn bullet_hits_ship_across_the_edge() {
+        let mut app = App::new();
+        app.init_resource::<Playfield>()
+            .init_resource::<ConsumedThisTick>()
+            .init_resource::<SpatialGrid>()
+            .init_resource::<SimIds>()
+            .insert_resource(BulletRules::default())
+            .add_event::<ShipHit>()
+            .add_systems(
+                Update,
+                (update_spatial_grid, check_bullet_player_collision).chain(),
+            );
+        let edge = Playfield::default().width / 2.0;
+        // The nose sticks out past the right edge, the bullet is where it
+        // comes back in on the left
+        let ship = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(edge - 5.0, 0.0, 0.0),
+                Physics::new(),
+                Player,
+                ShipInvincibility::new(0.0),
+                Hyperspace::default(),
+                ShipSize(16.0),
+                Collider { radius: 16.0 },
+            ))
+            .id();
+        let saucer = app.world_mut().spawn_empty().id();
+        app.world_mut().spawn((
+            Transform::from_xyz(-edge + 8.0, 0.0, 0.0),
+            Physics::new(),
+            Bullet {
+                owner: saucer,
+                team: Team::Saucers,
+            },
         ));
+
+        app.update();
+
+        let hits = app.world().resource::<Events<ShipHit>>();
+        let hits: Vec<_> = hits.get_cursor().read(hits).map(|hit| hit.ship).collect();
+        assert_eq!(hits, vec![ship]);
     }
 }
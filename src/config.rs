@@ -0,0 +1,221 @@
+use crate::difficulty::DifficultySettings;
+use crate::net::NetSession;
+use crate::scoring::ScoreTable;
+use bevy::app::{App, Plugin, Update};
+use bevy::log::{info, warn};
+use bevy::prelude::{IntoSystemConfigs, Res, ResMut, Resource, Time, not, resource_exists};
+use bevy::time::{Real, Timer, TimerMode};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Seconds between checks of the config file for changes
+const RELOAD_INTERVAL: f32 = 1.0;
+
+/// Gameplay tunables, read from a RON file so the game can be rebalanced
+/// without recompiling. Missing fields keep their default.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GameConfig {
+    /// Length of the ship from its back to its nose, and its hit radius
+    pub player_size: f32,
+    /// Asteroid size the split speeds and the points are measured against
+    pub asteroid_max_size: f32,
+    /// Speed of a blaster bullet, other weapons are relative to it
+    pub bullet_speed: f32,
+    // The Normal difficulty, Easy and Hard are derived from it
+    /// Seconds a ship can't be hit after (re)spawning
+    pub invincibility_time: f32,
+    /// Seconds between asteroid spawns at the start of a game
+    pub spawn_interval: f32,
+    /// The spawn interval is multiplied by this every 10 spawns
+    pub spawn_ramp: f32,
+    pub asteroid_speed: Range<f32>,
+    /// Pieces a large asteroid breaks into
+    pub split_count: i32,
+    pub lives: usize,
+    /// Settings of the Custom difficulty
+    pub custom_difficulty: DifficultySettings,
+    /// Points, combos and extra lives
+    pub score_table: ScoreTable,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        let normal = DifficultySettings::default();
+        Self {
+            player_size: 16.0,
+            asteroid_max_size: 60.0,
+            bullet_speed: 250.0,
+            invincibility_time: normal.invincibility_time,
+            spawn_interval: normal.spawn_interval,
+            spawn_ramp: normal.spawn_ramp,
+            asteroid_speed: normal.asteroid_speed.clone(),
+            split_count: normal.split_count,
+            lives: normal.lives,
+            custom_difficulty: normal,
+            score_table: ScoreTable::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Puts the default back for values the game can't be played with.
+    fn validate(&mut self) {
+        let default = Self::default();
+        let custom = &mut self.custom_difficulty;
+        let at_least_one = |value: &usize| *value >= 1;
+        let positive = |value: &f32| *value > 0.0;
+        let forwards = |speed: &Range<f32>| speed.start >= 0.0 && speed.start <= speed.end;
+        check("lives", &mut self.lives, default.lives, at_least_one);
+        check(
+            "custom_difficulty.lives",
+            &mut custom.lives,
+            default.custom_difficulty.lives,
+            at_least_one,
+        );
+        check(
+            "spawn_interval",
+            &mut self.spawn_interval,
+            default.spawn_interval,
+            positive,
+        );
+        check(
+            "custom_difficulty.spawn_interval",
+            &mut custom.spawn_interval,
+            default.custom_difficulty.spawn_interval,
+            positive,
+        );
+        check(
+            "asteroid_speed",
+            &mut self.asteroid_speed,
+            default.asteroid_speed,
+            forwards,
+        );
+        check(
+            "custom_difficulty.asteroid_speed",
+            &mut custom.asteroid_speed,
+            default.custom_difficulty.asteroid_speed,
+            forwards,
+        );
+        // Only set for the whole game, not per difficulty
+        check(
+            "player_size",
+            &mut self.player_size,
+            default.player_size,
+            positive,
+        );
+        check(
+            "asteroid_max_size",
+            &mut self.asteroid_max_size,
+            default.asteroid_max_size,
+            positive,
+        );
+        check(
+            "bullet_speed",
+            &mut self.bullet_speed,
+            default.bullet_speed,
+            positive,
+        );
+        check(
+            "score_table.max_multiplier",
+            &mut self.score_table.max_multiplier,
+            default.score_table.max_multiplier,
+            at_least_one,
+        );
+    }
+}
+
+fn check<T: Debug>(name: &str, value: &mut T, default: T, valid: impl Fn(&T) -> bool) {
+    if !valid(value) {
+        warn!("Rejected {name}: {value:?} in the config, using {default:?}");
+        *value = default;
+    }
+}
+
+/// The file [`GameConfig`] is loaded from, and when it last changed.
+#[derive(Resource)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl ConfigFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            timer: Timer::from_seconds(RELOAD_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+
+    /// Reads the config, or `None` if it is missing or broken.
+    fn load(&mut self) -> Option<GameConfig> {
+        self.modified = self.modified();
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Failed to read config {}: {e}", self.path.display());
+                return None;
+            }
+        };
+        match ron::from_str::<GameConfig>(&content) {
+            Ok(mut config) => {
+                config.validate();
+                Some(config)
+            }
+            Err(e) => {
+                warn!("Failed to parse config {}: {e}", self.path.display());
+                None
+            }
+        }
+    }
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self::new(PathBuf::from("assets/config.ron"))
+    }
+}
+
+/// Picks up changes to the config file while the game is running. Values
+/// read every tick apply at once, the rest from the next ship or game on.
+fn reload_config(
+    time: Res<Time<Real>>,
+    mut file: ResMut<ConfigFile>,
+    mut config: ResMut<GameConfig>,
+) {
+    if !file.timer.tick(time.delta()).just_finished() || file.modified() == file.modified {
+        return;
+    }
+    if let Some(new) = file.load()
+        && new != *config
+    {
+        info!("Reloaded config {}", file.path.display());
+        *config = new;
+    }
+}
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let mut file = ConfigFile::default();
+        let config = file.load().unwrap_or_default();
+        app.insert_resource(config)
+            .insert_resource(file)
+            // Online everyone has to play by the same numbers
+            .add_systems(
+                Update,
+                reload_config.run_if(not(resource_exists::<NetSession>)),
+            );
+    }
+}
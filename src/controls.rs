@@ -0,0 +1,576 @@
+use crate::helpers::user_data_dir;
+use crate::menu::{MenuAction, MenuButton, MenuStack};
+use crate::navigation::MenuNavigation;
+use crate::states::GameSets;
+use crate::ui::{spawn_menu_button, white_text};
+use bevy::app::{App, Plugin, PreUpdate, Update};
+use bevy::hierarchy::{BuildChildren, ChildBuild, ChildBuilder, Children};
+use bevy::input::ButtonInput;
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::log::warn;
+use bevy::prelude::{
+    AlignItems, Bundle, Changed, Component, Condition, DetectChanges, Entity, Interaction,
+    IntoSystemConfigs, IntoSystemSetConfigs, JustifyContent, KeyCode, Node, Query, Res, ResMut,
+    Resource, Text, Val, default, resource_changed,
+};
+use std::fs;
+use std::path::PathBuf;
+
+/// Players that can share one keyboard, each also gets a gamepad of their own.
+pub const LOCAL_PLAYERS: usize = 2;
+
+/// Something a player can do, bound to a key and a gamepad button.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    RotateLeft,
+    RotateRight,
+    Thrust,
+    Fire,
+    Hyperspace,
+    Shield,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Thrust,
+        Action::Fire,
+        Action::Hyperspace,
+        Action::Shield,
+        Action::Pause,
+    ];
+
+    /// Name in the bindings file.
+    fn key(self) -> &'static str {
+        match self {
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::Thrust => "thrust",
+            Action::Fire => "fire",
+            Action::Hyperspace => "hyperspace",
+            Action::Shield => "shield",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::RotateLeft => "Rotate left",
+            Action::RotateRight => "Rotate right",
+            Action::Thrust => "Thrust",
+            Action::Fire => "Fire",
+            Action::Hyperspace => "Hyperspace",
+            Action::Shield => "Shield",
+            Action::Pause => "Pause",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Keys that can be bound, anything else is ignored on the controls screen.
+const BINDABLE_KEYS: [KeyCode; 60] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+];
+
+const BINDABLE_BUTTONS: [GamepadButton; 19] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::C,
+    GamepadButton::Z,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::Mode,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+/// Short name of a key for the screen.
+pub fn key_label(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    match key {
+        KeyCode::Escape => "Esc".to_string(),
+        _ => ["Key", "Digit", "Arrow"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .unwrap_or(&name)
+            .to_string(),
+    }
+}
+
+/// Name of a button as printed on an Xbox style pad.
+fn button_label(button: GamepadButton) -> String {
+    let label = match button {
+        GamepadButton::South => "A",
+        GamepadButton::East => "B",
+        GamepadButton::North => "Y",
+        GamepadButton::West => "X",
+        GamepadButton::LeftTrigger => "LB",
+        GamepadButton::LeftTrigger2 => "LT",
+        GamepadButton::RightTrigger => "RB",
+        GamepadButton::RightTrigger2 => "RT",
+        GamepadButton::LeftThumb => "LS",
+        GamepadButton::RightThumb => "RS",
+        GamepadButton::DPadUp => "Up",
+        GamepadButton::DPadDown => "Down",
+        GamepadButton::DPadLeft => "Left",
+        GamepadButton::DPadRight => "Right",
+        other => return format!("{other:?}"),
+    };
+    label.to_string()
+}
+
+/// The key and the gamepad button of every action for one player.
+#[derive(Clone)]
+pub struct Controls {
+    keys: [KeyCode; Action::ALL.len()],
+    buttons: [GamepadButton; Action::ALL.len()],
+}
+
+impl Controls {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys[action.index()]
+    }
+
+    pub fn button(&self, action: Action) -> GamepadButton {
+        self.buttons[action.index()]
+    }
+
+    /// Held down on the keyboard or the player's gamepad.
+    pub fn pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        gamepad: Option<&Gamepad>,
+    ) -> bool {
+        keys.pressed(self.key(action)) || gamepad.is_some_and(|g| g.pressed(self.button(action)))
+    }
+
+    pub fn just_pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        gamepad: Option<&Gamepad>,
+    ) -> bool {
+        keys.just_pressed(self.key(action))
+            || gamepad.is_some_and(|g| g.just_pressed(self.button(action)))
+    }
+}
+
+/// Controls of the local players, stored in the user data directory as
+/// `<player> <action> key|pad <name>` lines. Actions not in the file keep
+/// their default.
+#[derive(Resource, Clone)]
+pub struct Bindings {
+    pub players: [Controls; LOCAL_PLAYERS],
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        // Both players have the same buttons, each on their own gamepad
+        let buttons = [
+            GamepadButton::DPadLeft,
+            GamepadButton::DPadRight,
+            GamepadButton::RightTrigger2,
+            GamepadButton::South,
+            GamepadButton::West,
+            GamepadButton::LeftTrigger2,
+            GamepadButton::Start,
+        ];
+        Self {
+            players: [
+                Controls {
+                    keys: [
+                        KeyCode::ArrowLeft,
+                        KeyCode::ArrowRight,
+                        KeyCode::KeyZ,
+                        KeyCode::KeyX,
+                        KeyCode::Space,
+                        KeyCode::ShiftLeft,
+                        KeyCode::Escape,
+                    ],
+                    buttons,
+                },
+                Controls {
+                    keys: [
+                        KeyCode::KeyJ,
+                        KeyCode::KeyL,
+                        KeyCode::KeyI,
+                        KeyCode::KeyK,
+                        KeyCode::KeyU,
+                        KeyCode::KeyH,
+                        KeyCode::KeyP,
+                    ],
+                    buttons,
+                },
+            ],
+        }
+    }
+}
+
+impl Bindings {
+    fn path() -> PathBuf {
+        user_data_dir().join("bindings.txt")
+    }
+
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        let Ok(content) = fs::read_to_string(Self::path()) else {
+            return bindings;
+        };
+        for line in content.lines() {
+            if bindings.apply(line).is_none() {
+                warn!("Ignoring binding \"{line}\"");
+            }
+        }
+        bindings
+    }
+
+    /// Binds `key` to the player's `action`. The keyboard is shared, so the
+    /// action of either player that had the key gets the old one instead.
+    fn bind_key(&mut self, player: usize, action: Action, key: KeyCode) {
+        let old = self.players[player].key(action);
+        for bound in self.players.iter_mut().flat_map(|c| &mut c.keys) {
+            if *bound == key {
+                *bound = old;
+            }
+        }
+        self.players[player].keys[action.index()] = key;
+    }
+
+    /// Binds `button` to the player's `action`, swapping it with the action
+    /// that had it on the player's gamepad.
+    fn bind_button(&mut self, player: usize, action: Action, button: GamepadButton) {
+        let controls = &mut self.players[player];
+        let old = controls.button(action);
+        for bound in &mut controls.buttons {
+            if *bound == button {
+                *bound = old;
+            }
+        }
+        controls.buttons[action.index()] = button;
+    }
+
+    fn apply(&mut self, line: &str) -> Option<()> {
+        let mut parts = line.split_whitespace();
+        let player: usize = parts.next()?.parse().ok()?;
+        let controls = self.players.get_mut(player.checked_sub(1)?)?;
+        let action = parts.next()?;
+        let action = Action::ALL.into_iter().find(|a| a.key() == action)?;
+        let (device, name) = (parts.next()?, parts.next()?);
+        match device {
+            "key" => {
+                controls.keys[action.index()] = BINDABLE_KEYS
+                    .into_iter()
+                    .find(|k| format!("{k:?}") == name)?;
+            }
+            "pad" => {
+                controls.buttons[action.index()] = BINDABLE_BUTTONS
+                    .into_iter()
+                    .find(|b| format!("{b:?}") == name)?;
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    pub fn save(&self) {
+        let path = Self::path();
+        let mut content = String::new();
+        for (i, controls) in self.players.iter().enumerate() {
+            for action in Action::ALL {
+                let (key, button) = (controls.key(action), controls.button(action));
+                content.push_str(&format!("{} {} key {key:?}\n", i + 1, action.key()));
+                content.push_str(&format!("{} {} pad {button:?}\n", i + 1, action.key()));
+            }
+        }
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, content));
+        if let Err(e) = result {
+            warn!("Failed to save bindings to {}: {e}", path.display());
+        }
+    }
+
+    /// Pressed by any local player.
+    pub fn any_just_pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        gamepads: &Query<&Gamepad>,
+    ) -> bool {
+        self.players.iter().any(|controls| {
+            keys.just_pressed(controls.key(action))
+                || gamepads
+                    .iter()
+                    .any(|g| g.just_pressed(controls.button(action)))
+        })
+    }
+
+    fn label(&self, target: RebindButton) -> String {
+        let controls = &self.players[target.player];
+        match target.device {
+            Device::Key => key_label(controls.key(target.action)),
+            Device::Pad => button_label(controls.button(target.action)),
+        }
+    }
+}
+
+/// The gamepad of each local player, in the order they were connected.
+pub fn local_gamepads<'a>(gamepads: &'a Query<(Entity, &Gamepad)>) -> Vec<&'a Gamepad> {
+    let mut pads: Vec<_> = gamepads.iter().collect();
+    pads.sort_by_key(|(entity, _)| *entity);
+    pads.into_iter().map(|(_, pad)| pad).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Device {
+    Key,
+    Pad,
+}
+
+/// Waits for a new key or button for the action.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RebindButton {
+    player: usize,
+    action: Action,
+    device: Device,
+}
+
+/// The binding waiting for a key or button press.
+#[derive(Resource, Default)]
+pub struct Rebinding(Option<RebindButton>);
+
+fn label(text: impl Into<String>, width: f32) -> impl Bundle {
+    (
+        white_text(text, 16.0),
+        Node {
+            width: Val::Px(width),
+            ..default()
+        },
+    )
+}
+
+fn spawn_rebind_button(parent: &mut ChildBuilder, bindings: &Bindings, target: RebindButton) {
+    spawn_menu_button(parent, &bindings.label(target), target).insert(Node {
+        width: Val::Px(96.),
+        height: Val::Px(26.),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    });
+}
+
+/// A row for every action, with a button for each player's key and pad.
+pub fn spawn_bindings(parent: &mut ChildBuilder, bindings: &Bindings) {
+    let row = Node {
+        column_gap: Val::Px(8.),
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    parent.spawn(row.clone()).with_children(|parent| {
+        parent.spawn(label("", 110.0));
+        for header in ["P1 KEY", "P1 PAD", "P2 KEY", "P2 PAD"] {
+            parent.spawn(label(header, 96.0));
+        }
+    });
+    for action in Action::ALL {
+        parent.spawn(row.clone()).with_children(|parent| {
+            parent.spawn(label(action.label(), 110.0));
+            for player in 0..LOCAL_PLAYERS {
+                for device in [Device::Key, Device::Pad] {
+                    let target = RebindButton {
+                        player,
+                        action,
+                        device,
+                    };
+                    spawn_rebind_button(parent, bindings, target);
+                }
+            }
+        });
+    }
+}
+
+type ControlsMenuButton<'a> = (
+    &'a Interaction,
+    Option<&'a RebindButton>,
+    Option<&'a MenuButton>,
+);
+
+pub fn controls_menu(
+    stack: Res<MenuStack>,
+    mut bindings: ResMut<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+    buttons: Query<ControlsMenuButton, Changed<Interaction>>,
+) {
+    // Leaving the screen gives up on the binding
+    if stack.is_changed() {
+        rebinding.0 = None;
+    }
+    for (interaction, rebind, menu_button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(target) = rebind {
+            rebinding.0 = Some(*target);
+        }
+        if let Some(MenuButton(MenuAction::ResetControls)) = menu_button {
+            *bindings = Bindings::default();
+            bindings.save();
+            rebinding.0 = None;
+        }
+    }
+}
+
+/// Binds the next key or gamepad button pressed.
+pub fn capture_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+) {
+    // The press that picked the binding shouldn't become it
+    let Some(target) = rebinding.0.filter(|_| !rebinding.is_changed()) else {
+        return;
+    };
+    match target.device {
+        Device::Key => {
+            let Some(key) = keys.get_just_pressed().find(|k| BINDABLE_KEYS.contains(k)) else {
+                return;
+            };
+            bindings.bind_key(target.player, target.action, *key);
+        }
+        Device::Pad => {
+            let Some(button) = gamepads
+                .iter()
+                .flat_map(|g| g.get_just_pressed())
+                .find(|b| BINDABLE_BUTTONS.contains(b))
+            else {
+                return;
+            };
+            bindings.bind_button(target.player, target.action, *button);
+        }
+    }
+    rebinding.0 = None;
+    bindings.save();
+}
+
+pub fn update_binding_labels(
+    bindings: Res<Bindings>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&RebindButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (target, children) in &buttons {
+        let label = if rebinding.0 == Some(*target) {
+            "...".to_string()
+        } else {
+            bindings.label(*target)
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.0.clone_from(&label);
+            }
+        }
+    }
+}
+
+fn not_rebinding(rebinding: Res<Rebinding>) -> bool {
+    rebinding.0.is_none()
+}
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bindings::load())
+            .init_resource::<Rebinding>()
+            // The arrows, Enter and Escape can be bound too
+            .configure_sets(PreUpdate, MenuNavigation.run_if(not_rebinding))
+            .add_systems(
+                Update,
+                (
+                    controls_menu,
+                    capture_binding,
+                    update_binding_labels
+                        .run_if(resource_changed::<Bindings>.or(resource_changed::<Rebinding>)),
+                )
+                    .chain()
+                    .in_set(GameSets::Menu),
+            );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::config::GameConfig;
+use bevy::prelude::{Component, Res, ResMut, Resource};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    /// Whatever the `custom_difficulty` section of the config says
+    Custom,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Custom,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Custom => "Custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// The tunables a difficulty changes, in effect for the current game.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DifficultySettings {
+    /// Seconds between asteroid spawns at the start of a game
+    pub spawn_interval: f32,
+    /// The spawn interval is multiplied by this every 10 spawns
+    pub spawn_ramp: f32,
+    /// Speed of newly spawned asteroids
+    pub asteroid_speed: Range<f32>,
+    /// Pieces a large asteroid breaks into
+    pub split_count: i32,
+    pub lives: usize,
+    /// Seconds a ship can't be hit after (re)spawning
+    pub invincibility_time: f32,
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        Self {
+            spawn_interval: 6.0,
+            spawn_ramp: 0.9,
+            asteroid_speed: 10.0..20.0,
+            split_count: 2,
+            lives: 3,
+            invincibility_time: 2.0,
+        }
+    }
+}
+
+impl DifficultySettings {
+    fn normal(config: &GameConfig) -> Self {
+        Self {
+            spawn_interval: config.spawn_interval,
+            spawn_ramp: config.spawn_ramp,
+            asteroid_speed: config.asteroid_speed.clone(),
+            split_count: config.split_count,
+            lives: config.lives,
+            invincibility_time: config.invincibility_time,
+        }
+    }
+
+    pub fn new(difficulty: Difficulty, config: &GameConfig) -> Self {
+        let normal = Self::normal(config);
+        let speed = normal.asteroid_speed.clone();
+        match difficulty {
+            Difficulty::Easy => Self {
+                spawn_interval: normal.spawn_interval * 1.5,
+                // Ramps up half as fast
+                spawn_ramp: 1.0 - (1.0 - normal.spawn_ramp) / 2.0,
+                asteroid_speed: speed.start * 0.75..speed.end * 0.75,
+                lives: normal.lives + 2,
+                invincibility_time: normal.invincibility_time * 1.5,
+                ..normal
+            },
+            Difficulty::Normal => normal,
+            Difficulty::Hard => Self {
+                spawn_interval: normal.spawn_interval * 0.6,
+                spawn_ramp: 1.0 - (1.0 - normal.spawn_ramp) * 1.5,
+                asteroid_speed: speed.start * 1.5..speed.end * 1.5,
+                split_count: normal.split_count + 1,
+                lives: normal.lives.saturating_sub(1).max(1),
+                invincibility_time: normal.invincibility_time * 0.5,
+            },
+            Difficulty::Custom => config.custom_difficulty.clone(),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DifficultyUi;
+
+/// Settles the tunables of a new game, and brings a reloaded config into the
+/// game being played.
+pub fn update_difficulty_settings(
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut settings: ResMut<DifficultySettings>,
+) {
+    *settings = DifficultySettings::new(*difficulty, &config);
+}
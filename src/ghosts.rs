@@ -0,0 +1,101 @@
+use crate::transforming::Playfield;
+use bevy::math::{BVec2, Vec2};
+use bevy::prelude::{
+    Added, Changed, Commands, Component, Entity, Query, Res, Transform, Visibility, With, Without,
+    default,
+};
+use bevy_prototype_lyon::draw::Stroke;
+use bevy_prototype_lyon::entity::{Path, ShapeBundle};
+
+/// Shape that is drawn a second time on the opposite edge of the playfield
+/// while it straddles one, so it slides across instead of popping over.
+#[derive(Component)]
+pub struct GhostRendered {
+    pub radius: f32,
+}
+
+/// Copy of a `GhostRendered` shape, shifted by the playfield size along the
+/// axes it is responsible for.
+#[derive(Component)]
+pub struct Ghost {
+    source: Entity,
+    axes: BVec2,
+}
+
+pub fn spawn_ghosts(
+    mut commands: Commands,
+    shapes: Query<(Entity, &Path, &Stroke), Added<GhostRendered>>,
+) {
+    for (entity, path, stroke) in &shapes {
+        // One for each edge and one for the corner
+        for axes in [
+            BVec2::new(true, false),
+            BVec2::new(false, true),
+            BVec2::TRUE,
+        ] {
+            commands.spawn((
+                ShapeBundle {
+                    path: path.clone(),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                *stroke,
+                Ghost {
+                    source: entity,
+                    axes,
+                },
+            ));
+        }
+    }
+}
+
+/// Redraws the ghosts of shapes whose outline changed after they spawned.
+pub fn update_ghost_paths(
+    sources: Query<&Path, (Changed<Path>, Without<Ghost>)>,
+    mut ghosts: Query<(&Ghost, &mut Path)>,
+) {
+    for (ghost, mut path) in &mut ghosts {
+        if let Ok(source) = sources.get(ghost.source) {
+            *path = source.clone();
+        }
+    }
+}
+
+fn wrap_offset(pos: f32, radius: f32, size: f32) -> f32 {
+    if pos + radius > size / 2.0 {
+        -size
+    } else if pos - radius < -size / 2.0 {
+        size
+    } else {
+        0.0
+    }
+}
+
+pub fn update_ghosts(
+    mut commands: Commands,
+    playfield: Res<Playfield>,
+    sources: Query<(&Transform, &Visibility, &GhostRendered), Without<Ghost>>,
+    mut ghosts: Query<(Entity, &Ghost, &mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    for (entity, ghost, mut transform, mut visibility) in &mut ghosts {
+        let Ok((source, source_visibility, wraps)) = sources.get(ghost.source) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let pos = source.translation;
+        let offset = Vec2::new(
+            wrap_offset(pos.x, wraps.radius, playfield.width),
+            wrap_offset(pos.y, wraps.radius, playfield.height),
+        );
+        let straddling = (offset.x != 0.0 || !ghost.axes.x) && (offset.y != 0.0 || !ghost.axes.y);
+        if !straddling {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *transform = *source;
+        transform.translation += Vec2::select(ghost.axes, offset, Vec2::ZERO).extend(0.0);
+        *visibility = *source_visibility;
+    }
+}
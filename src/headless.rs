@@ -0,0 +1,143 @@
+use crate::actions::gather_local_input;
+use crate::controls::{Action, Bindings};
+use crate::net::net_tick_ready;
+use crate::player::PlayerId;
+use crate::states::{GameResourcesPlugin, GameStates, TICK_RATE};
+use crate::ui::Score;
+use bevy::app::{App, AppExit, FixedUpdate, Plugin, PostUpdate, Startup};
+use bevy::input::{ButtonInput, InputPlugin, InputSystem};
+use bevy::log::{LogPlugin, info};
+use bevy::prelude::{
+    DetectChanges, EventWriter, IntoSystemConfigs, KeyCode, MinimalPlugins, NextState, OnEnter,
+    PreUpdate, Query, Res, ResMut, Resource, resource_exists,
+};
+use bevy::state::app::{AppExtStates, StatesPlugin};
+use bevy::time::TimeUpdateStrategy;
+use fastrand::Rng;
+use std::time::Duration;
+
+/// Runs the game without a window or GPU. Every `App::update` advances real
+/// time by exactly one fixed tick, so sessions can also be driven manually.
+/// Slowed time takes a few updates per tick, so ticks are counted as they run.
+pub struct HeadlessPlugin {
+    pub max_ticks: Option<u64>,
+    /// Seed for a bot at the first player's keys
+    pub bot: Option<u64>,
+}
+
+#[derive(Resource)]
+pub struct HeadlessSession {
+    pub ticks: u64,
+    pub max_ticks: Option<u64>,
+}
+
+/// Mashes the first player's keys at random, so there is someone playing.
+#[derive(Resource)]
+pub struct Bot(Rng);
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            MinimalPlugins,
+            LogPlugin::default(),
+            StatesPlugin,
+            InputPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / TICK_RATE,
+        )))
+        // There is no one to press "Play", so go straight into a game
+        .insert_state(GameStates::Menu)
+        .add_systems(Startup, start_game)
+        .add_plugins(GameResourcesPlugin)
+        .insert_resource(HeadlessSession {
+            ticks: 0,
+            max_ticks: self.max_ticks,
+        })
+        .add_systems(
+            PreUpdate,
+            press_random_keys
+                .after(InputSystem)
+                .before(gather_local_input)
+                .run_if(resource_exists::<Bot>),
+        )
+        .add_systems(FixedUpdate, count_ticks.run_if(net_tick_ready))
+        // Ahead of everything that finishes up on exit in `Last`
+        .add_systems(PostUpdate, check_tick_limit)
+        .add_systems(OnEnter(GameStates::GameOver), end_session);
+        if let Some(seed) = self.bot {
+            app.insert_resource(Bot(Rng::with_seed(seed)));
+        }
+    }
+}
+
+fn press_random_keys(
+    mut bot: ResMut<Bot>,
+    bindings: Res<Bindings>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+) {
+    for action in Action::ALL {
+        // Hyperspace now and then, the rest a lot
+        let chance = match action {
+            Action::Pause => continue,
+            Action::Hyperspace => 0.002,
+            _ => 0.1,
+        };
+        let key = bindings.players[0].key(action);
+        if bot.0.f32() >= chance {
+            continue;
+        }
+        if keys.pressed(key) {
+            keys.release(key);
+        } else {
+            keys.press(key);
+        }
+    }
+}
+
+fn start_game(mut next_state: ResMut<NextState<GameStates>>) {
+    next_state.set(GameStates::Playing);
+}
+
+/// The score of every player in order, like `1200 / 850`.
+fn scores(ships: &Query<(&PlayerId, &Score)>) -> String {
+    let mut scores: Vec<_> = ships.iter().map(|(id, score)| (id.0, **score)).collect();
+    scores.sort();
+    scores
+        .iter()
+        .map(|(_, score)| score.to_string())
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn count_ticks(mut session: ResMut<HeadlessSession>) {
+    session.ticks += 1;
+}
+
+fn check_tick_limit(
+    session: Res<HeadlessSession>,
+    ships: Query<(&PlayerId, &Score)>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if session.is_changed() && session.max_ticks.is_some_and(|max| session.ticks >= max) {
+        info!(
+            "Tick limit reached after {} ticks, score: {}",
+            session.ticks,
+            scores(&ships)
+        );
+        exit.send(AppExit::Success);
+    }
+}
+
+fn end_session(
+    session: Res<HeadlessSession>,
+    ships: Query<(&PlayerId, &Score)>,
+    mut exit: EventWriter<AppExit>,
+) {
+    info!(
+        "Game over after {} ticks, score: {}",
+        session.ticks,
+        scores(&ships)
+    );
+    exit.send(AppExit::Success);
+}
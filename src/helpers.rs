@@ -1,27 +1,43 @@
+use bevy::ecs::query::QueryFilter;
 use bevy::hierarchy::DespawnRecursiveExt;
 use bevy::math::Vec2;
-use bevy::prelude::{Commands, Component, Entity, Query, With};
+use bevy::prelude::{Commands, Entity, Query};
+use fastrand::Rng;
+use std::env;
 use std::f32::consts::PI;
 use std::ops::Range;
+use std::path::PathBuf;
 
-pub fn random_range(range: Range<f32>) -> f32 {
-    fastrand::f32() * (range.end - range.start) + range.start
+pub fn random_range(rng: &mut Rng, range: Range<f32>) -> f32 {
+    rng.f32() * (range.end - range.start) + range.start
 }
 
-pub fn despawn_query<T: Component>(commands: &mut Commands, query: Query<Entity, With<T>>) {
+pub fn despawn_query<F: QueryFilter>(commands: &mut Commands, query: Query<Entity, F>) {
     for entity in &query {
         commands.entity(entity).despawn();
     }
 }
-pub fn despawn_recursive_query<T: Component>(
-    commands: &mut Commands,
-    query: Query<Entity, With<T>>,
-) {
+pub fn despawn_recursive_query<F: QueryFilter>(commands: &mut Commands, query: Query<Entity, F>) {
     for entity in &query {
         commands.entity(entity).despawn_recursive();
     }
 }
 
-pub fn get_random_vel(range: Range<f32>) -> Vec2 {
-    Vec2::from_angle(random_range(0.0..PI * 2.0)) * random_range(range)
+pub fn get_random_vel(rng: &mut Rng, range: Range<f32>) -> Vec2 {
+    Vec2::from_angle(random_range(rng, 0.0..PI * 2.0)) * random_range(rng, range)
+}
+
+/// Per-user directory for files that persist between runs.
+pub fn user_data_dir() -> PathBuf {
+    let home = || env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|h| h.join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home().map(|h| h.join(".local/share")))
+    };
+    base.unwrap_or_default().join("bevy-asteroids")
 }
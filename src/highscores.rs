@@ -0,0 +1,342 @@
+use crate::actions::InputPlayback;
+use crate::helpers::{despawn_recursive_query, user_data_dir};
+use crate::player::{GameMode, PlayerId};
+use crate::states::{GameSets, GameStates};
+use crate::ui::{PlayerLives, Score, white_text};
+use bevy::app::{App, Plugin, Update};
+use bevy::hierarchy::{BuildChildren, ChildBuild};
+use bevy::input::ButtonInput;
+use bevy::input::ButtonState;
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::log::warn;
+use bevy::prelude::{
+    AlignItems, Commands, Component, Entity, EventReader, FlexDirection, IntoSystemConfigs,
+    JustifyContent, KeyCode, NextState, Node, OnEnter, OnExit, Query, Res, ResMut, Resource,
+    Single, Text, Time, Val, With, default, resource_exists,
+};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::PathBuf;
+
+pub const MAX_ENTRIES: usize = 10;
+const INITIALS_LEN: usize = 3;
+/// Seconds before any key leaves the screen, so a key held while dying
+/// doesn't skip it
+const GAME_OVER_GRACE: f32 = 1.0;
+/// Seconds before the screen goes back to the menu on its own
+const GAME_OVER_TIMEOUT: f32 = 5.0;
+/// Seconds to enter initials in before the score is kept with what is there
+const INITIALS_TIMEOUT: f32 = 30.0;
+/// Characters the D-pad steps through for an initial
+const INITIALS_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+pub struct HighScoreEntry {
+    pub initials: String,
+    pub score: usize,
+}
+
+/// Top scores, best first, stored as `<initials> <score>` lines in the user
+/// data directory.
+#[derive(Resource, Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    fn path() -> PathBuf {
+        user_data_dir().join("highscores.txt")
+    }
+
+    pub fn load() -> Self {
+        let Ok(content) = fs::read_to_string(Self::path()) else {
+            return Self::default();
+        };
+        let mut entries: Vec<HighScoreEntry> = content
+            .lines()
+            .filter_map(|line| {
+                let (initials, score) = line.rsplit_once(' ')?;
+                Some(HighScoreEntry {
+                    initials: initials.to_string(),
+                    score: score.parse().ok()?,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|e| Reverse(e.score));
+        entries.truncate(MAX_ENTRIES);
+        Self { entries }
+    }
+
+    pub fn save(&self) {
+        let path = Self::path();
+        let content: String = self
+            .entries
+            .iter()
+            .map(|e| format!("{} {}\n", e.initials, e.score))
+            .collect();
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, content));
+        if let Err(e) = result {
+            warn!("Failed to save high scores to {}: {e}", path.display());
+        }
+    }
+
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|e| score > e.score))
+    }
+
+    pub fn insert(&mut self, initials: String, score: usize) {
+        // Ties go to whoever got there first
+        let index = self.entries.partition_point(|e| e.score >= score);
+        self.entries
+            .insert(index, HighScoreEntry { initials, score });
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+#[derive(Component)]
+pub struct GameOverUi;
+
+#[derive(Component)]
+pub struct InitialsText;
+
+/// Present while the player is typing initials for a new high score.
+#[derive(Resource, Default)]
+pub struct InitialsEntry {
+    /// The best score of the game, players share one entry
+    score: usize,
+    initials: String,
+    /// The initial being picked with the D-pad, not entered yet
+    picking: Option<char>,
+    /// Key presses from before the prompt appeared have been thrown away
+    ready: bool,
+    /// Seconds the prompt has been up
+    time: f32,
+}
+
+impl InitialsEntry {
+    fn enter_picked(&mut self) {
+        self.initials.extend(self.picking.take());
+    }
+}
+
+/// Present while the plain game over screen is shown.
+#[derive(Resource, Default)]
+pub struct GameOverTimer(f32);
+
+fn initials_display(initials: &str, picking: Option<char>) -> String {
+    let mut display: Vec<char> = initials.chars().chain(picking).collect();
+    display.resize(INITIALS_LEN, '_');
+    display
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn setup_game_over(
+    mut commands: Commands,
+    mode: Res<GameMode>,
+    ships: Query<(&PlayerId, &Score, &PlayerLives)>,
+    high_scores: Res<HighScores>,
+    playback: Option<Res<InputPlayback>>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    // Demos go straight back to the menu
+    if playback.is_some_and(|p| p.is_attract()) {
+        next_state.set(GameStates::Menu);
+        return;
+    }
+
+    let mut ships: Vec<_> = ships.iter().collect();
+    ships.sort_by_key(|(id, ..)| id.0);
+    let best = ships
+        .iter()
+        .map(|(_, score, _)| ***score)
+        .max()
+        .unwrap_or_default();
+    let new_high_score = high_scores.qualifies(best);
+    if new_high_score {
+        commands.insert_resource(InitialsEntry {
+            score: best,
+            ..default()
+        });
+    } else {
+        commands.insert_resource(GameOverTimer::default());
+    }
+
+    commands
+        .spawn((
+            GameOverUi,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(white_text("GAME OVER", 40.0));
+            if let [(_, score, _)] = ships[..] {
+                parent.spawn(white_text(score.to_string(), 24.0));
+            } else {
+                for (id, score, _) in &ships {
+                    parent.spawn(white_text(format!("P{} {}", id.0 + 1, ***score), 24.0));
+                }
+            }
+            // The last ship flying wins
+            let survivor = ships.iter().find(|(.., lives)| ***lives > 0);
+            if *mode == GameMode::Versus
+                && let Some((id, ..)) = survivor
+            {
+                parent.spawn(white_text(format!("PLAYER {} WINS", id.0 + 1), 24.0));
+            }
+            if new_high_score {
+                parent.spawn(white_text("NEW HIGH SCORE - enter your initials", 18.0));
+                parent.spawn((InitialsText, white_text(initials_display("", None), 32.0)));
+            } else {
+                parent.spawn(white_text("Press any key", 18.0));
+            }
+        });
+}
+
+pub fn leave_game_over(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut timer: ResMut<GameOverTimer>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    timer.0 += time.delta_secs();
+    let pressed = keys.get_just_pressed().next().is_some()
+        || gamepads
+            .iter()
+            .any(|g| g.get_just_pressed().next().is_some());
+    let key_pressed = timer.0 > GAME_OVER_GRACE && pressed;
+    if key_pressed || timer.0 > GAME_OVER_TIMEOUT {
+        next_state.set(GameStates::Menu);
+    }
+}
+
+/// The character `steps` further along [`INITIALS_ALPHABET`], wrapping around.
+fn step_initial(from: char, steps: isize) -> char {
+    let alphabet: Vec<char> = INITIALS_ALPHABET.chars().collect();
+    let index = alphabet.iter().position(|c| *c == from).unwrap_or_default();
+    let len = alphabet.len() as isize;
+    alphabet[(index as isize + steps).rem_euclid(len) as usize]
+}
+
+fn save_initials(
+    entry: &InitialsEntry,
+    high_scores: &mut HighScores,
+    next_state: &mut NextState<GameStates>,
+) {
+    high_scores.insert(entry.initials.clone(), entry.score);
+    high_scores.save();
+    next_state.set(GameStates::Menu);
+}
+
+/// Initials are typed, or picked with the D-pad: up and down change the
+/// initial, A enters it, B takes the last one back and Start is done.
+pub fn enter_initials(
+    time: Res<Time>,
+    mut keyboard: EventReader<KeyboardInput>,
+    gamepads: Query<&Gamepad>,
+    mut entry: ResMut<InitialsEntry>,
+    mut high_scores: ResMut<HighScores>,
+    mut initials_text: Single<&mut Text, With<InitialsText>>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if !entry.ready {
+        keyboard.clear();
+        entry.ready = true;
+        return;
+    }
+    entry.time += time.delta_secs();
+    if entry.time > INITIALS_TIMEOUT {
+        entry.enter_picked();
+        if entry.initials.is_empty() {
+            entry.initials = "?".repeat(INITIALS_LEN);
+        }
+        save_initials(&entry, &mut high_scores, &mut next_state);
+        return;
+    }
+
+    let pressed = |button| gamepads.iter().any(|g| g.just_pressed(button));
+    let full = entry.initials.len() >= INITIALS_LEN;
+    if pressed(GamepadButton::DPadUp) && !full {
+        entry.picking = Some(entry.picking.map_or('A', |c| step_initial(c, 1)));
+    } else if pressed(GamepadButton::DPadDown) && !full {
+        entry.picking = Some(entry.picking.map_or('9', |c| step_initial(c, -1)));
+    } else if pressed(GamepadButton::South) {
+        entry.enter_picked();
+    } else if pressed(GamepadButton::East) {
+        if entry.picking.take().is_none() {
+            entry.initials.pop();
+        }
+    } else if pressed(GamepadButton::Start) {
+        entry.enter_picked();
+        if !entry.initials.is_empty() {
+            save_initials(&entry, &mut high_scores, &mut next_state);
+            return;
+        }
+    }
+
+    for event in keyboard.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(c) => {
+                let Some(c) = c.chars().next().filter(char::is_ascii_alphanumeric) else {
+                    continue;
+                };
+                entry.picking = None;
+                if entry.initials.len() < INITIALS_LEN {
+                    entry.initials.push(c.to_ascii_uppercase());
+                }
+            }
+            Key::Backspace => {
+                entry.picking = None;
+                entry.initials.pop();
+            }
+            Key::Enter if !entry.initials.is_empty() => {
+                save_initials(&entry, &mut high_scores, &mut next_state);
+                return;
+            }
+            _ => {}
+        }
+    }
+    initials_text.0 = initials_display(&entry.initials, entry.picking);
+}
+
+pub fn cleanup_game_over(mut commands: Commands, ui: Query<Entity, With<GameOverUi>>) {
+    despawn_recursive_query(&mut commands, ui);
+    commands.remove_resource::<InitialsEntry>();
+    commands.remove_resource::<GameOverTimer>();
+}
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_systems(OnEnter(GameStates::GameOver), setup_game_over)
+            .add_systems(
+                Update,
+                (
+                    enter_initials.run_if(resource_exists::<InitialsEntry>),
+                    leave_game_over.run_if(resource_exists::<GameOverTimer>),
+                )
+                    .in_set(GameSets::GameOver),
+            )
+            .add_systems(OnExit(GameStates::GameOver), cleanup_game_over);
+    }
+}
@@ -1,3 +1,4 @@
+use bevy::hierarchy::DespawnRecursiveExt;
 use bevy::prelude::{Commands, Component, Entity, Query, Res, Time};
 
 #[derive(Component)]
@@ -19,7 +20,7 @@ pub fn check_lifetime(
     for (entity, mut lifetime) in &mut lifetimes {
         lifetime.time_left -= time.delta_secs();
         if lifetime.time_left <= 0.0 {
-            commands.entity(entity).despawn()
+            commands.entity(entity).despawn_recursive()
         }
     }
 }
@@ -1,39 +1,243 @@
+mod actions;
 mod asteroid;
 mod bullet;
 mod collision;
+mod config;
+mod controls;
+mod difficulty;
+mod ghosts;
+mod headless;
 mod helpers;
+mod highscores;
 mod lifetime;
+mod menu;
+mod navigation;
+mod net;
+mod pause;
 mod player;
+mod polygon;
+mod powerup;
+mod rng;
+mod saucer;
+mod scoring;
+mod shield;
+mod spatial;
 mod spawner;
 mod states;
 mod transforming;
 mod ui;
+mod weapon;
 
+use crate::actions::{
+    ActionsPlugin, InputPlayback, InputRecorder, MAX_PLAYERS, Recording, apply_playback,
+};
+use crate::config::GameConfig;
+use crate::controls::ControlsPlugin;
+use crate::difficulty::{Difficulty, update_difficulty_settings};
+use crate::headless::HeadlessPlugin;
+use crate::highscores::HighScoresPlugin;
+use crate::menu::MenuPlugin;
+use crate::navigation::NavigationPlugin;
+use crate::net::{NetOptions, NetPlugin, NetSession, net_tick_ready};
+use crate::pause::PausePlugin;
+use crate::player::{GameMode, apply_game_mode};
+use crate::rng::GameRng;
+use crate::shield::ShieldSettings;
 use crate::states::{
-    GameSets, GameStates, InitPlugin, PlayingPlugin, cleanup_menu, cleanup_playing, setup_menu,
-    setup_playing_state,
+    GameSets, GameStates, InitPlugin, PlayingPlugin, cleanup_menu, cleanup_playing,
+    setup_playing_state, spawn_hud,
 };
-use crate::ui::{Score, main_menu};
 use bevy::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
 
 fn main() {
     let mut app = App::new();
 
-    app.add_plugins((InitPlugin, PlayingPlugin))
-        // Run in menu state and playing state
-        .add_systems(Update, transforming::move_objects)
-        .add_systems(Update, transforming::wrap_objects)
-        .add_systems(Update, main_menu.in_set(GameSets::Menu))
-        .add_systems(
-            OnEnter(GameStates::Playing),
-            (cleanup_menu, cleanup_playing, setup_playing_state).chain(),
+    let mut args = std::env::args().skip(1);
+    let mut headless = false;
+    let mut max_ticks = None;
+    let mut bot = None;
+    let mut seed = None;
+    let mut record = None;
+    let mut replay = None;
+    let mut shield = false;
+    let mut difficulty = None;
+    let mut mode = None;
+    let mut host = None;
+    let mut join = None;
+    let mut players = 2;
+    let mut net_options = NetOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => headless = true,
+            "--ticks" => max_ticks = args.next().and_then(|t| t.parse().ok()),
+            "--bot" => bot = args.next().and_then(|s| s.parse().ok()),
+            "--seed" => seed = args.next().and_then(|s| s.parse().ok()),
+            "--record" => record = args.next().map(PathBuf::from),
+            "--replay" => replay = args.next().map(PathBuf::from),
+            "--shield" => shield = true,
+            "--difficulty" => difficulty = args.next().and_then(|d| Difficulty::from_name(&d)),
+            "--mode" => mode = args.next().and_then(|m| GameMode::from_key(&m)),
+            "--host" => host = args.next().and_then(|p| p.parse::<u16>().ok()),
+            "--join" => join = args.next(),
+            "--players" => {
+                players = args
+                    .next()
+                    .and_then(|p| p.parse().ok())
+                    .unwrap_or(players)
+                    .clamp(2, MAX_PLAYERS)
+            }
+            "--input-delay" => {
+                if let Some(delay) = args.next().and_then(|d| d.parse().ok()) {
+                    net_options.input_delay = delay;
+                }
+            }
+            "--latency" => {
+                if let Some(ms) = args.next().and_then(|l| l.parse().ok()) {
+                    net_options.latency = Duration::from_millis(ms);
+                }
+            }
+            "--loss" => {
+                if let Some(percent) = args.next().and_then(|l| l.parse::<f32>().ok()) {
+                    net_options.loss = percent / 100.0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if headless {
+        app.add_plugins(HeadlessPlugin { max_ticks, bot });
+    } else {
+        app.add_plugins(InitPlugin);
+    }
+    if let Some(path) = record {
+        app.insert_resource(InputRecorder::new(path));
+    }
+    if let Some(path) = replay {
+        match Recording::load(&path) {
+            Ok(recording) => {
+                // The recording only reproduces the game with the seed it was made with
+                seed = Some(recording.seed);
+                app.insert_resource(InputPlayback::new(recording));
+            }
+            Err(e) => error!("Failed to load replay {}: {e}", path.display()),
+        }
+    }
+    // Blocks until everyone is there, the host decides what is played
+    let session = match (host, join) {
+        (Some(port), _) => {
+            let seed = *seed.get_or_insert_with(|| fastrand::u64(..));
+            let difficulty = difficulty.unwrap_or_default();
+            let config = app.world().resource::<GameConfig>().clone();
+            Some(NetSession::host(
+                port,
+                players,
+                seed,
+                difficulty,
+                shield,
+                config,
+                &net_options,
+            ))
+        }
+        (None, Some(addr)) => Some(NetSession::join(&addr, &net_options)),
+        (None, None) => None,
+    };
+    match session {
+        Some(Ok(session)) => {
+            seed = Some(session.seed());
+            difficulty = Some(session.difficulty());
+            mode = Some(GameMode::Online(session.players()));
+            shield = session.shield();
+            app.insert_resource(session.config().clone());
+            app.insert_resource(session);
+        }
+        Some(Err(e)) => {
+            error!("Failed to start online game: {e}");
+            return;
+        }
+        None => {}
+    }
+    if shield {
+        app.insert_resource(ShieldSettings {
+            enabled: true,
+            ..default()
+        });
+    }
+    if let Some(difficulty) = difficulty {
+        app.insert_resource(difficulty);
+    }
+    if let Some(mode) = mode {
+        app.insert_resource(mode);
+    }
+    if let Some(seed) = seed {
+        info!("Using seed {seed} for every game");
+    }
+    app.insert_resource(seed.map(GameRng::new).unwrap_or_default());
+
+    app.add_plugins((
+        PlayingPlugin,
+        ActionsPlugin,
+        PausePlugin,
+        HighScoresPlugin,
+        NetPlugin,
+        ControlsPlugin,
+        NavigationPlugin,
+        MenuPlugin,
+    ))
+    // Run in every state but paused
+    .add_systems(
+        FixedUpdate,
+        (transforming::move_objects, transforming::wrap_objects)
+            .chain()
+            .run_if(not(in_state(GameStates::Paused)).and(net_tick_ready))
+            .before(GameSets::Playing),
+    )
+    .add_systems(
+        Update,
+        (
+            ghosts::spawn_ghosts,
+            ghosts::update_ghost_paths,
+            ghosts::update_ghosts,
         )
-        .add_systems(OnEnter(GameStates::Menu), setup_menu);
+            .chain(),
+    )
+    // Resuming from pause also enters Playing, only a new game sets up
+    .add_systems(
+        OnTransition {
+            exited: GameStates::Menu,
+            entered: GameStates::Playing,
+        },
+        (
+            cleanup_menu,
+            apply_playback,
+            update_difficulty_settings,
+            apply_game_mode,
+            setup_playing_state,
+            spawn_hud,
+        )
+            .chain(),
+    )
+    // However the game ended, quit from pause, over or a demo cut short
+    .add_systems(OnEnter(GameStates::Menu), cleanup_playing);
 
     app.configure_sets(
         Update,
         GameSets::Playing.run_if(in_state(GameStates::Playing)),
     );
+    app.configure_sets(
+        FixedUpdate,
+        GameSets::Playing.run_if(in_state(GameStates::Playing).and(net_tick_ready)),
+    );
     app.configure_sets(Update, GameSets::Menu.run_if(in_state(GameStates::Menu)));
+    app.configure_sets(
+        Update,
+        GameSets::Paused.run_if(in_state(GameStates::Paused)),
+    );
+    app.configure_sets(
+        Update,
+        GameSets::GameOver.run_if(in_state(GameStates::GameOver)),
+    );
     app.run();
 }
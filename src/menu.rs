@@ -0,0 +1,311 @@
+use crate::controls::{Bindings, spawn_bindings};
+use crate::difficulty::Difficulty;
+use crate::highscores::HighScores;
+use crate::player::GameMode;
+use crate::shield::ShieldSettings;
+use crate::states::{GameSets, GameStates};
+use crate::ui::{BackButton, MenuUi, spawn_menu_button, white_text};
+use bevy::app::{App, Plugin, Update};
+use bevy::hierarchy::{BuildChildren, ChildBuild, ChildBuilder, DespawnRecursiveExt};
+use bevy::prelude::{
+    AlignItems, AppExit, Changed, Commands, Component, Entity, EventWriter, FlexDirection,
+    Interaction, IntoSystemConfigs, JustifyContent, NextState, Node, OnEnter, Query, Res, ResMut,
+    Resource, Time, Timer, TimerMode, Val, With, default, resource_changed,
+};
+
+/// How far a new screen starts off to the side, in percent of the window.
+const SLIDE_DISTANCE: f32 = 30.0;
+const SLIDE_TIME: f32 = 0.2;
+
+const CREDITS: [&str; 4] = [
+    "After Asteroids by Atari, 1979",
+    "Made with Bevy",
+    "Shapes drawn with bevy_prototype_lyon",
+    "Thanks for playing!",
+];
+
+/// One screen of the main menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuScreen {
+    Main,
+    /// Picks the game mode
+    Play,
+    /// Picks the difficulty, then starts the game
+    Difficulty,
+    Options,
+    Controls,
+    HighScores,
+    Credits,
+    /// Asks before quitting
+    Quit,
+}
+
+/// What pressing a menu button does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Open(MenuScreen),
+    Back,
+    PickMode(GameMode),
+    Start(Difficulty),
+    ToggleShield,
+    ResetControls,
+    Exit,
+}
+
+#[derive(Component)]
+pub struct MenuButton(pub MenuAction);
+
+impl MenuScreen {
+    fn title(self) -> &'static str {
+        match self {
+            MenuScreen::Main => "ASTEROIDS",
+            MenuScreen::Play => "MODE",
+            MenuScreen::Difficulty => "DIFFICULTY",
+            MenuScreen::Options => "OPTIONS",
+            MenuScreen::Controls => "CONTROLS",
+            MenuScreen::HighScores => "HIGH SCORES",
+            MenuScreen::Credits => "CREDITS",
+            MenuScreen::Quit => "QUIT THE GAME?",
+        }
+    }
+
+    /// The buttons of the screen from top to bottom.
+    fn buttons(self, shield: &ShieldSettings) -> Vec<(String, MenuAction)> {
+        let back = ("Back".to_string(), MenuAction::Back);
+        match self {
+            MenuScreen::Main => [
+                ("Play", MenuScreen::Play),
+                ("Options", MenuScreen::Options),
+                ("Controls", MenuScreen::Controls),
+                ("High Scores", MenuScreen::HighScores),
+                ("Credits", MenuScreen::Credits),
+                ("Quit", MenuScreen::Quit),
+            ]
+            .into_iter()
+            .map(|(label, screen)| (label.to_string(), MenuAction::Open(screen)))
+            .collect(),
+            MenuScreen::Play => GameMode::ALL
+                .into_iter()
+                .map(|mode| (mode.label().to_string(), MenuAction::PickMode(mode)))
+                .chain([back])
+                .collect(),
+            MenuScreen::Difficulty => Difficulty::ALL
+                .into_iter()
+                .map(|difficulty| (difficulty.name().to_string(), MenuAction::Start(difficulty)))
+                .chain([back])
+                .collect(),
+            MenuScreen::Options => {
+                let state = if shield.enabled { "On" } else { "Off" };
+                vec![(format!("Shield: {state}"), MenuAction::ToggleShield), back]
+            }
+            MenuScreen::Controls => vec![("Reset".to_string(), MenuAction::ResetControls), back],
+            MenuScreen::HighScores | MenuScreen::Credits => vec![back],
+            MenuScreen::Quit => vec![
+                ("Quit".to_string(), MenuAction::Exit),
+                ("Cancel".to_string(), MenuAction::Back),
+            ],
+        }
+    }
+
+    fn row_gap(self) -> f32 {
+        match self {
+            // The bindings table needs the room
+            MenuScreen::Controls => 6.0,
+            _ => 24.0,
+        }
+    }
+}
+
+/// The screens opened on the way to the one shown, which is the last. There is
+/// always at least the main screen.
+#[derive(Resource)]
+pub struct MenuStack {
+    screens: Vec<MenuScreen>,
+    /// Where the shown screen slides in from, negative for the left
+    slide_from: f32,
+}
+
+impl Default for MenuStack {
+    fn default() -> Self {
+        Self {
+            screens: vec![MenuScreen::Main],
+            slide_from: 0.0,
+        }
+    }
+}
+
+impl MenuStack {
+    pub fn top(&self) -> MenuScreen {
+        *self.screens.last().unwrap_or(&MenuScreen::Main)
+    }
+
+    fn push(&mut self, screen: MenuScreen) {
+        self.screens.push(screen);
+        self.slide_from = SLIDE_DISTANCE;
+    }
+
+    fn pop(&mut self) {
+        if self.screens.len() > 1 {
+            self.screens.pop();
+        }
+        self.slide_from = -SLIDE_DISTANCE;
+    }
+
+    /// Builds the shown screen again, for when its labels changed.
+    fn refresh(&mut self) {
+        self.slide_from = 0.0;
+    }
+}
+
+/// Eases a new screen in from the side.
+#[derive(Component)]
+pub struct MenuSlide {
+    from: f32,
+    timer: Timer,
+}
+
+fn spawn_high_scores(parent: &mut ChildBuilder, high_scores: &HighScores) {
+    if high_scores.entries.is_empty() {
+        parent.spawn(white_text("No scores yet", 16.0));
+        return;
+    }
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            for (rank, entry) in high_scores.entries.iter().enumerate() {
+                parent.spawn(white_text(
+                    format!("{:>2}. {:<3} {:>8}", rank + 1, entry.initials, entry.score),
+                    16.0,
+                ));
+            }
+        });
+}
+
+pub fn setup_menu(mut stack: ResMut<MenuStack>) {
+    *stack = MenuStack::default();
+}
+
+/// Replaces the shown screen with the top of the stack.
+pub fn show_menu_screen(
+    mut commands: Commands,
+    stack: Res<MenuStack>,
+    menu: Query<Entity, With<MenuUi>>,
+    bindings: Res<Bindings>,
+    high_scores: Res<HighScores>,
+    shield: Res<ShieldSettings>,
+) {
+    for e in &menu {
+        commands.entity(e).despawn_recursive();
+    }
+    let screen = stack.top();
+    commands
+        .spawn((
+            MenuUi,
+            MenuSlide {
+                from: stack.slide_from,
+                timer: Timer::from_seconds(SLIDE_TIME, TimerMode::Once),
+            },
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(screen.row_gap()),
+                left: Val::Percent(stack.slide_from),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(white_text(screen.title(), 20.0));
+            match screen {
+                MenuScreen::Controls => spawn_bindings(parent, &bindings),
+                MenuScreen::HighScores => spawn_high_scores(parent, &high_scores),
+                MenuScreen::Credits => {
+                    for line in CREDITS {
+                        parent.spawn(white_text(line, 16.0));
+                    }
+                }
+                _ => {}
+            }
+            for (label, action) in screen.buttons(&shield) {
+                if action == MenuAction::Back {
+                    spawn_menu_button(parent, &label, (MenuButton(action), BackButton));
+                } else {
+                    spawn_menu_button(parent, &label, MenuButton(action));
+                }
+            }
+        });
+}
+
+pub fn slide_menu(time: Res<Time>, mut screens: Query<(&mut Node, &mut MenuSlide)>) {
+    for (mut node, mut slide) in &mut screens {
+        if slide.timer.finished() {
+            continue;
+        }
+        slide.timer.tick(time.delta());
+        let left = 1.0 - slide.timer.fraction();
+        node.left = Val::Percent(slide.from * left * left);
+    }
+}
+
+pub fn press_menu_button(
+    mut stack: ResMut<MenuStack>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut shield: ResMut<ShieldSettings>,
+    mut next_state: ResMut<NextState<GameStates>>,
+    mut exit: EventWriter<AppExit>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+) {
+    for (interaction, MenuButton(action)) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *action {
+            MenuAction::Open(screen) => stack.push(screen),
+            MenuAction::Back => stack.pop(),
+            // The mode is picked first, then the difficulty
+            MenuAction::PickMode(choice) => {
+                *mode = choice;
+                stack.push(MenuScreen::Difficulty);
+            }
+            MenuAction::Start(choice) => {
+                *difficulty = choice;
+                next_state.set(GameStates::Playing);
+            }
+            MenuAction::ToggleShield => {
+                shield.enabled = !shield.enabled;
+                stack.refresh();
+            }
+            // Done by the controls screen
+            MenuAction::ResetControls => {}
+            MenuAction::Exit => {
+                exit.send(AppExit::Success);
+            }
+        }
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuStack>()
+            .add_systems(OnEnter(GameStates::Menu), setup_menu)
+            .add_systems(
+                Update,
+                (
+                    press_menu_button,
+                    show_menu_screen.run_if(resource_changed::<MenuStack>),
+                    slide_menu,
+                )
+                    .chain()
+                    .in_set(GameSets::Menu),
+            );
+    }
+}
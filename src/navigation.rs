@@ -0,0 +1,171 @@
+use crate::ui::BackButton;
+use bevy::app::{App, Plugin, PreUpdate, Update};
+use bevy::hierarchy::Children;
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::input::{ButtonInput, InputSystem};
+use bevy::math::Vec2;
+use bevy::prelude::{
+    Button, Changed, DetectChanges, Entity, GlobalTransform, Interaction, IntoSystemConfigs,
+    IntoSystemSetConfigs, KeyCode, Local, Query, Res, ResMut, Resource, SystemSet, TextFont, With,
+};
+use bevy::ui::UiSystem;
+
+const FONT_SIZE: f32 = 18.0;
+const FOCUSED_FONT_SIZE: f32 = 22.0;
+
+/// The button that Enter or the gamepad's A presses. Moved with the arrow keys
+/// or the D-pad, and by hovering with the mouse.
+#[derive(Resource, Default)]
+pub struct MenuFocus(Option<Entity>);
+
+/// Moves the focus and presses buttons. Screens that need the keys for
+/// something else, like picking a binding, can hold it off with a run
+/// condition on this set.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuNavigation;
+
+/// Where a button is on the screen, in UI pixels with y going down.
+fn position(transform: &GlobalTransform) -> Vec2 {
+    transform.translation().truncate()
+}
+
+/// The closest button in `direction`, preferring ones in line with `from`.
+fn neighbour(
+    from: Vec2,
+    direction: Vec2,
+    buttons: impl Iterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    buttons
+        .filter_map(|(entity, at)| {
+            let offset = at - from;
+            let along = offset.dot(direction);
+            (along > 1.0).then_some((entity, along + 2.0 * offset.perp_dot(direction).abs()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(entity, _)| entity)
+}
+
+/// Which way the arrow keys or any D-pad asks to move the focus this frame.
+fn direction(keys: &ButtonInput<KeyCode>, gamepads: &Query<&Gamepad>) -> Option<Vec2> {
+    let pressed =
+        |key, button| keys.just_pressed(key) || gamepads.iter().any(|g| g.just_pressed(button));
+    [
+        (KeyCode::ArrowUp, GamepadButton::DPadUp, Vec2::NEG_Y),
+        (KeyCode::ArrowDown, GamepadButton::DPadDown, Vec2::Y),
+        (KeyCode::ArrowLeft, GamepadButton::DPadLeft, Vec2::NEG_X),
+        (KeyCode::ArrowRight, GamepadButton::DPadRight, Vec2::X),
+    ]
+    .into_iter()
+    .find(|(key, button, _)| pressed(*key, *button))
+    .map(|(_, _, direction)| direction)
+}
+
+pub fn navigate_menu(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut focus: ResMut<MenuFocus>,
+    // Pressed from here last frame, there is no mouse release to undo it
+    mut pressed: Local<Option<Entity>>,
+    mut buttons: Query<(Entity, &mut Interaction, &GlobalTransform), With<Button>>,
+    back: Query<Entity, With<BackButton>>,
+) {
+    if let Some(entity) = pressed.take()
+        && let Ok((_, mut interaction, _)) = buttons.get_mut(entity)
+        && *interaction == Interaction::Pressed
+    {
+        *interaction = Interaction::None;
+    }
+
+    if let Some((entity, ..)) = buttons.iter_mut().find(|(_, interaction, _)| {
+        interaction.is_changed() && **interaction == Interaction::Hovered
+    }) {
+        focus.0 = Some(entity);
+    }
+    // The focused button went away with its screen, start at the top of the
+    // next one
+    if focus.0.is_none_or(|entity| !buttons.contains(entity)) {
+        let top = buttons
+            .iter()
+            .map(|(entity, _, transform)| (entity, position(transform)))
+            .min_by(|a, b| a.1.y.total_cmp(&b.1.y).then(a.1.x.total_cmp(&b.1.x)))
+            .map(|(entity, _)| entity);
+        if focus.0 != top {
+            focus.0 = top;
+        }
+    }
+    let Some(focused) = focus.0 else {
+        return;
+    };
+
+    if let Some(direction) = direction(&keys, &gamepads) {
+        let Ok((.., transform)) = buttons.get(focused) else {
+            return;
+        };
+        let from = position(transform);
+        let others = buttons
+            .iter()
+            .filter(|(entity, ..)| *entity != focused)
+            .map(|(entity, _, transform)| (entity, position(transform)));
+        if let Some(next) = neighbour(from, direction, others) {
+            focus.0 = Some(next);
+        }
+        return;
+    }
+
+    let confirm = keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter])
+        || gamepads
+            .iter()
+            .any(|g| g.just_pressed(GamepadButton::South));
+    let cancel = keys.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|g| g.just_pressed(GamepadButton::East));
+    let target = match (confirm, cancel) {
+        (true, _) => Some(focused),
+        (_, true) => back.iter().next(),
+        _ => None,
+    };
+    if let Some(target) = target
+        && let Ok((_, mut interaction, _)) = buttons.get_mut(target)
+    {
+        *interaction = Interaction::Pressed;
+        *pressed = Some(target);
+    }
+}
+
+/// Grows the label of the focused button, the same way hovering always did.
+pub fn highlight_focused_button(
+    focus: Res<MenuFocus>,
+    buttons: Query<(Entity, &Interaction, &Children), With<Button>>,
+    changed: Query<(), (Changed<Interaction>, With<Button>)>,
+    mut text_query: Query<&mut TextFont>,
+) {
+    if !focus.is_changed() && changed.is_empty() {
+        return;
+    }
+    for (entity, interaction, children) in &buttons {
+        let font_size = match *interaction {
+            Interaction::Pressed => FONT_SIZE,
+            _ if focus.0 == Some(entity) => FOCUSED_FONT_SIZE,
+            _ => FONT_SIZE,
+        };
+        for x in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*x) {
+                text.font_size = font_size;
+            }
+        }
+    }
+}
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            // After the mouse has had its say on the buttons
+            .configure_sets(
+                PreUpdate,
+                MenuNavigation.after(InputSystem).after(UiSystem::Focus),
+            )
+            .add_systems(PreUpdate, navigate_menu.in_set(MenuNavigation))
+            .add_systems(Update, highlight_focused_button);
+    }
+}
@@ -0,0 +1,812 @@
+use crate::actions::{PlayerActions, PlayerInputs, TickInputs};
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::states::GameStates;
+use crate::transforming::{Physics, move_objects};
+use crate::ui::Score;
+use bevy::app::{App, AppExit, FixedUpdate, Last, Plugin, Startup};
+use bevy::log::{error, info, warn};
+use bevy::prelude::{
+    Commands, Condition, IntoSystemConfigs, NextState, OnEnter, Query, Res, ResMut, Resource,
+    Transform, With, in_state, on_event, resource_exists,
+};
+use fastrand::Rng;
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for everyone to join.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the other side may stay silent before the game is given up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the host makes sure the clients have the last frames after the game.
+const FINISH_TIMEOUT: Duration = Duration::from_secs(1);
+/// Pause between looks at the socket while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// How often data the other side hasn't confirmed yet is sent again.
+const RESEND_INTERVAL: Duration = Duration::from_millis(20);
+/// Most ticks of input in one packet.
+const MAX_TICKS_PER_PACKET: usize = 64;
+/// Ticks between comparisons of the host's and the clients' simulation.
+const CHECKSUM_INTERVAL: usize = 60;
+
+const HELLO: u8 = 1;
+const WELCOME: u8 = 2;
+const INPUTS: u8 = 3;
+const FRAMES: u8 = 4;
+
+/// How the online session is run, mostly for trying it out on one machine.
+#[derive(Clone, Copy, Debug)]
+pub struct NetOptions {
+    /// Ticks between sampling the local input and simulating it, so it has
+    /// time to reach everyone else before they need it
+    pub input_delay: usize,
+    /// Added to every outgoing packet
+    pub latency: Duration,
+    /// Chance from 0 to 1 that an outgoing packet is dropped
+    pub loss: f32,
+}
+
+impl Default for NetOptions {
+    fn default() -> Self {
+        Self {
+            input_delay: 6,
+            latency: Duration::ZERO,
+            loss: 0.0,
+        }
+    }
+}
+
+/// What goes over the wire. Ticks count from the start of the game, and every
+/// side tells the other how much of its data has arrived so only the rest is
+/// sent again.
+#[derive(Debug)]
+enum Message {
+    /// Asks the host for a place in the game
+    Hello,
+    /// The host's answer, with the game to play and the rules to play it by
+    Welcome {
+        player: u8,
+        players: u8,
+        difficulty: u8,
+        seed: u64,
+        shield: bool,
+        config: GameConfig,
+    },
+    /// A client's own actions from tick `first` on, and the simulation
+    /// checksum of a recent tick
+    Inputs {
+        received: u32,
+        first: u32,
+        checksum: Option<(u32, u64)>,
+        actions: Vec<u16>,
+    },
+    /// The actions of every player from tick `first` on
+    Frames {
+        received: u32,
+        first: u32,
+        players: u8,
+        actions: Vec<u16>,
+    },
+}
+
+impl Message {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        match self {
+            Message::Hello => out.push(HELLO),
+            Message::Welcome {
+                player,
+                players,
+                difficulty,
+                seed,
+                shield,
+                config,
+            } => {
+                out.extend([WELCOME, *player, *players, *difficulty]);
+                out.extend(seed.to_le_bytes());
+                out.push(u8::from(*shield));
+                // Serializing a config that was deserialized can't fail
+                out.extend(ron::to_string(config).unwrap_or_default().into_bytes());
+            }
+            Message::Inputs {
+                received,
+                first,
+                checksum,
+                actions,
+            } => {
+                out.push(INPUTS);
+                out.extend(received.to_le_bytes());
+                out.extend(first.to_le_bytes());
+                match checksum {
+                    Some((tick, hash)) => {
+                        out.push(1);
+                        out.extend(tick.to_le_bytes());
+                        out.extend(hash.to_le_bytes());
+                    }
+                    None => out.push(0),
+                }
+                out.extend(actions.iter().flat_map(|a| a.to_le_bytes()));
+            }
+            Message::Frames {
+                received,
+                first,
+                players,
+                actions,
+            } => {
+                out.push(FRAMES);
+                out.extend(received.to_le_bytes());
+                out.extend(first.to_le_bytes());
+                out.push(*players);
+                out.extend(actions.iter().flat_map(|a| a.to_le_bytes()));
+            }
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        let message = match reader.u8()? {
+            HELLO => Message::Hello,
+            WELCOME => Message::Welcome {
+                player: reader.u8()?,
+                players: reader.u8()?,
+                difficulty: reader.u8()?,
+                seed: reader.u64()?,
+                shield: reader.u8()? != 0,
+                config: ron::from_str(str::from_utf8(reader.rest()).ok()?).ok()?,
+            },
+            INPUTS => Message::Inputs {
+                received: reader.u32()?,
+                first: reader.u32()?,
+                checksum: match reader.u8()? {
+                    0 => None,
+                    _ => Some((reader.u32()?, reader.u64()?)),
+                },
+                actions: reader.actions()?,
+            },
+            FRAMES => {
+                let received = reader.u32()?;
+                let first = reader.u32()?;
+                let players = reader.u8()?;
+                let actions = reader.actions()?;
+                if players == 0 || actions.len() % players as usize != 0 {
+                    return None;
+                }
+                Message::Frames {
+                    received,
+                    first,
+                    players,
+                    actions,
+                }
+            }
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.0.split_first_chunk()?;
+        self.0 = rest;
+        Some(*bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take().map(u8::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn rest(&mut self) -> &[u8] {
+        mem::take(&mut self.0)
+    }
+
+    /// The rest of the packet as action masks.
+    fn actions(&mut self) -> Option<Vec<u16>> {
+        let (masks, rest) = self.0.as_chunks::<2>();
+        if !rest.is_empty() {
+            return None;
+        }
+        self.0 = rest;
+        Some(masks.iter().map(|m| u16::from_le_bytes(*m)).collect())
+    }
+}
+
+/// The socket, with the simulated latency and packet loss of [`NetOptions`]
+/// put on everything sent.
+struct Link {
+    socket: UdpSocket,
+    latency: Duration,
+    loss: f32,
+    /// Not the game's random numbers, which have to stay the same everywhere
+    rng: Rng,
+    in_flight: VecDeque<(Instant, SocketAddr, Vec<u8>)>,
+}
+
+impl Link {
+    fn new(socket: UdpSocket, options: &NetOptions) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            latency: options.latency,
+            loss: options.loss,
+            rng: Rng::new(),
+            in_flight: VecDeque::new(),
+        })
+    }
+
+    fn send(&mut self, to: SocketAddr, message: &Message) {
+        if self.rng.f32() < self.loss {
+            return;
+        }
+        self.in_flight
+            .push_back((Instant::now() + self.latency, to, message.encode()));
+        self.flush();
+    }
+
+    /// Sends the packets that have been held back long enough.
+    fn flush(&mut self) {
+        let now = Instant::now();
+        while self.in_flight.front().is_some_and(|(due, ..)| *due <= now) {
+            let Some((_, to, bytes)) = self.in_flight.pop_front() else {
+                break;
+            };
+            // A packet that can't be sent is just another lost packet
+            let _ = self.socket.send_to(&bytes, to);
+        }
+    }
+
+    fn recv(&mut self) -> Option<(SocketAddr, Message)> {
+        let mut buf = [0; 2048];
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, from)) => {
+                    if let Some(message) = Message::decode(&buf[..len]) {
+                        return Some((from, message));
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return None,
+                // Such as a peer that has gone away, noticed by the timeouts
+                Err(_) => {}
+            }
+        }
+    }
+}
+
+/// A client as the host sees it.
+struct Remote {
+    addr: SocketAddr,
+    /// The client's actions by tick
+    inputs: Vec<PlayerActions>,
+    /// Frames the client has confirmed
+    acked: usize,
+    last_heard: Instant,
+    out_of_sync: bool,
+}
+
+enum Role {
+    /// Player one. Collects everyone's actions and hands out the frames.
+    Host { clients: Vec<Remote> },
+    Client {
+        host: SocketAddr,
+        /// Actions of ours the host has confirmed
+        acked: usize,
+        last_heard: Instant,
+    },
+}
+
+/// An online game. Every peer runs the whole simulation, which is
+/// deterministic, so only the players' actions are exchanged: the host settles
+/// the actions of every player for each tick into a frame, and no one
+/// simulates a tick before its frame is known. Local actions are delayed by a
+/// few ticks to give them time to get around, so the game only waits when the
+/// network is slower than that. Waiting holds back the fixed ticks, the frames
+/// carry on being drawn.
+///
+/// Everyone has to play with the same config, a reload mid-game isn't applied.
+#[derive(Resource)]
+pub struct NetSession {
+    link: Link,
+    role: Role,
+    player: usize,
+    players: usize,
+    difficulty: Difficulty,
+    seed: u64,
+    shield: bool,
+    /// The host's, played by everyone
+    config: GameConfig,
+    /// The local player's actions by the tick they are simulated on
+    local: Vec<PlayerActions>,
+    /// Everyone's actions for the ticks settled so far
+    frames: Vec<TickInputs>,
+    /// The next tick to simulate
+    tick: usize,
+    /// Simulation checksums of every `CHECKSUM_INTERVAL`th tick
+    checksums: Vec<u64>,
+    last_sent: Instant,
+    /// The frame of the next tick wasn't known when last looked for
+    waiting: bool,
+}
+
+impl NetSession {
+    fn new(link: Link, role: Role, player: usize, players: usize, options: &NetOptions) -> Self {
+        Self {
+            link,
+            role,
+            player,
+            players,
+            difficulty: Difficulty::default(),
+            seed: 0,
+            shield: false,
+            config: GameConfig::default(),
+            // No one has pressed anything before the game starts
+            local: vec![PlayerActions::default(); options.input_delay],
+            frames: vec![],
+            tick: 0,
+            checksums: vec![],
+            last_sent: Instant::now(),
+            waiting: false,
+        }
+    }
+
+    /// Waits on `port` until `players - 1` others have joined, who all play
+    /// the game described by the rest.
+    pub fn host(
+        port: u16,
+        players: usize,
+        seed: u64,
+        difficulty: Difficulty,
+        shield: bool,
+        config: GameConfig,
+        options: &NetOptions,
+    ) -> io::Result<Self> {
+        let link = Link::new(UdpSocket::bind(("0.0.0.0", port))?, options)?;
+        let role = Role::Host { clients: vec![] };
+        let mut session = Self::new(link, role, 0, players, options);
+        session.seed = seed;
+        session.difficulty = difficulty;
+        session.shield = shield;
+        session.config = config;
+
+        info!(
+            "Hosting on port {port}, waiting for {} players",
+            players - 1
+        );
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        while session.joined() < players {
+            if Instant::now() > deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "not everyone joined",
+                ));
+            }
+            session.poll();
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(session)
+    }
+
+    /// Joins the game hosted at `addr` and learns which one it is.
+    pub fn join(addr: &str, options: &NetOptions) -> io::Result<Self> {
+        let host = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unknown host"))?;
+        let mut link = Link::new(UdpSocket::bind(("0.0.0.0", 0))?, options)?;
+
+        info!("Joining {host}");
+        let deadline = Instant::now() + JOIN_TIMEOUT;
+        let mut last_hello = None::<Instant>;
+        loop {
+            if Instant::now() > deadline {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "no answer"));
+            }
+            if last_hello.is_none_or(|t| t.elapsed() > RESEND_INTERVAL * 5) {
+                link.send(host, &Message::Hello);
+                last_hello = Some(Instant::now());
+            }
+            link.flush();
+            while let Some((from, message)) = link.recv() {
+                let Message::Welcome {
+                    player,
+                    players,
+                    difficulty,
+                    seed,
+                    shield,
+                    config,
+                } = message
+                else {
+                    continue;
+                };
+                if from != host {
+                    continue;
+                }
+                let role = Role::Client {
+                    host,
+                    acked: 0,
+                    last_heard: Instant::now(),
+                };
+                let mut session = Self::new(link, role, player.into(), players.into(), options);
+                session.seed = seed;
+                session.difficulty = Difficulty::ALL
+                    .get(usize::from(difficulty))
+                    .copied()
+                    .unwrap_or_default();
+                session.shield = shield;
+                session.config = config;
+                info!("Joined as player {}", session.player + 1);
+                return Ok(session);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn shield(&self) -> bool {
+        self.shield
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Whether the next tick has to wait for the other players.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    fn joined(&self) -> usize {
+        match &self.role {
+            Role::Host { clients } => clients.len() + 1,
+            Role::Client { .. } => self.players,
+        }
+    }
+
+    /// Takes in everything that has arrived.
+    fn poll(&mut self) {
+        self.link.flush();
+        let joining = self.frames.is_empty() && self.joined() < self.players;
+        let welcome = |player: usize| Message::Welcome {
+            player: player as u8,
+            players: self.players as u8,
+            difficulty: Difficulty::ALL
+                .iter()
+                .position(|d| *d == self.difficulty)
+                .unwrap_or_default() as u8,
+            seed: self.seed,
+            shield: self.shield,
+            config: self.config.clone(),
+        };
+        while let Some((from, message)) = self.link.recv() {
+            match (&mut self.role, message) {
+                (Role::Host { clients }, Message::Hello) => {
+                    // Latecomers are turned away, a lost welcome is sent again
+                    if joining && !clients.iter().any(|c| c.addr == from) {
+                        clients.push(Remote {
+                            addr: from,
+                            inputs: vec![],
+                            acked: 0,
+                            last_heard: Instant::now(),
+                            out_of_sync: false,
+                        });
+                        info!("Player {} joined from {from}", clients.len() + 1);
+                    }
+                    if let Some(i) = clients.iter().position(|c| c.addr == from) {
+                        self.link.send(from, &welcome(i + 1));
+                    }
+                }
+                (
+                    Role::Host { clients },
+                    Message::Inputs {
+                        received,
+                        first,
+                        checksum,
+                        actions,
+                    },
+                ) => {
+                    let Some(i) = clients.iter().position(|c| c.addr == from) else {
+                        continue;
+                    };
+                    let client = &mut clients[i];
+                    client.last_heard = Instant::now();
+                    client.acked = client.acked.max(received as usize);
+                    let actions = actions.into_iter().map(PlayerActions::from_bits);
+                    append(&mut client.inputs, first as usize, actions);
+
+                    let Some((tick, hash)) = checksum else {
+                        continue;
+                    };
+                    let ours = self.checksums.get(tick as usize / CHECKSUM_INTERVAL);
+                    if !client.out_of_sync && ours.is_some_and(|ours| *ours != hash) {
+                        client.out_of_sync = true;
+                        warn!("Player {} is out of sync since tick {tick}", i + 2);
+                    }
+                }
+                (
+                    Role::Client {
+                        host,
+                        acked,
+                        last_heard,
+                    },
+                    Message::Frames {
+                        received,
+                        first,
+                        players,
+                        actions,
+                    },
+                ) if from == *host => {
+                    *last_heard = Instant::now();
+                    *acked = (*acked).max(received as usize);
+                    let frames = actions.chunks(players.into()).map(|masks| {
+                        let mut frame = TickInputs::default();
+                        for (actions, mask) in frame.iter_mut().zip(masks) {
+                            *actions = PlayerActions::from_bits(*mask);
+                        }
+                        frame
+                    });
+                    append(&mut self.frames, first as usize, frames);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Settles the frames everyone's actions are known for.
+    fn settle_frames(&mut self) {
+        let Role::Host { clients } = &self.role else {
+            return;
+        };
+        let known = clients
+            .iter()
+            .map(|c| c.inputs.len())
+            .fold(self.local.len(), usize::min);
+        for tick in self.frames.len()..known {
+            let mut frame = TickInputs::default();
+            frame[0] = self.local[tick];
+            for (i, client) in clients.iter().enumerate() {
+                frame[i + 1] = client.inputs[tick];
+            }
+            self.frames.push(frame);
+        }
+    }
+
+    /// Sends the other side whatever it hasn't confirmed yet.
+    fn send_updates(&mut self) {
+        self.last_sent = Instant::now();
+        match &self.role {
+            Role::Host { clients } => {
+                for client in clients {
+                    let end = self.frames.len().min(client.acked + MAX_TICKS_PER_PACKET);
+                    let actions = self.frames[client.acked.min(end)..end]
+                        .iter()
+                        .flat_map(|frame| frame[..self.players].iter().map(|a| a.to_bits()))
+                        .collect();
+                    let message = Message::Frames {
+                        received: client.inputs.len() as u32,
+                        first: client.acked as u32,
+                        players: self.players as u8,
+                        actions,
+                    };
+                    self.link.send(client.addr, &message);
+                }
+            }
+            Role::Client { host, acked, .. } => {
+                let end = self.local.len().min(acked + MAX_TICKS_PER_PACKET);
+                let actions = self.local[(*acked).min(end)..end]
+                    .iter()
+                    .map(|a| a.to_bits())
+                    .collect();
+                let checksum = self
+                    .checksums
+                    .len()
+                    .checked_sub(1)
+                    .map(|i| ((i * CHECKSUM_INTERVAL) as u32, self.checksums[i]));
+                let message = Message::Inputs {
+                    received: self.frames.len() as u32,
+                    first: *acked as u32,
+                    checksum,
+                    actions,
+                };
+                self.link.send(*host, &message);
+            }
+        }
+    }
+
+    fn check_timeout(&self) -> io::Result<()> {
+        let silent = match &self.role {
+            Role::Host { clients } => clients.iter().any(|c| c.last_heard.elapsed() > TIMEOUT),
+            Role::Client { last_heard, .. } => last_heard.elapsed() > TIMEOUT,
+        };
+        if silent {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "lost contact with the other players",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Takes in what has arrived without waiting for more, and notes whether
+    /// the next tick can be simulated.
+    fn update(&mut self) -> io::Result<()> {
+        self.poll();
+        self.settle_frames();
+        self.waiting = self.frames.len() <= self.tick;
+        if self.waiting {
+            self.check_timeout()?;
+        }
+        if self.last_sent.elapsed() > RESEND_INTERVAL {
+            self.send_updates();
+        }
+        Ok(())
+    }
+
+    /// Hands in the local player's actions, to be simulated a few ticks from
+    /// now, and returns everyone's actions for this tick if they are known.
+    fn advance(&mut self, local: PlayerActions) -> Option<TickInputs> {
+        let frame = self.frames.get(self.tick).copied()?;
+        self.local.push(local);
+        self.send_updates();
+        self.tick += 1;
+        Some(frame)
+    }
+
+    /// Whether the tick just simulated is compared with the other peers.
+    fn wants_checksum(&self) -> bool {
+        self.tick > self.checksums.len() * CHECKSUM_INTERVAL
+    }
+
+    /// Lingers until the other side has everything it needs to end the game
+    /// too, or it looks like it never will.
+    fn finish(&mut self) {
+        // Peers that stayed in sync come to the same sum of all checksums
+        let mut hasher = DefaultHasher::new();
+        self.checksums.hash(&mut hasher);
+        info!(
+            "Online game over after {} ticks, checksums {:016x}",
+            self.tick,
+            hasher.finish()
+        );
+        self.send_updates();
+        let deadline = Instant::now() + FINISH_TIMEOUT;
+        while Instant::now() < deadline {
+            self.poll();
+            let done = match &self.role {
+                Role::Host { clients } => clients.iter().all(|c| c.acked >= self.frames.len()),
+                Role::Client { .. } => true,
+            };
+            if done && self.link.in_flight.is_empty() {
+                return;
+            }
+            if self.last_sent.elapsed() > RESEND_INTERVAL {
+                self.send_updates();
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Adds the `items` starting at tick `first` that carry on where `list` ends.
+/// Ones already there are skipped, and a gap means the packet in between was
+/// lost and will be sent again.
+fn append<T>(list: &mut Vec<T>, first: usize, items: impl Iterator<Item = T>) {
+    if first <= list.len() {
+        let known = list.len() - first;
+        list.extend(items.skip(known));
+    }
+}
+
+/// Looks for the other players' actions before every fixed tick.
+pub fn poll_net_session(
+    mut commands: Commands,
+    mut session: ResMut<NetSession>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if let Err(e) = session.update() {
+        error!("Online game ended: {e}");
+        commands.remove_resource::<NetSession>();
+        next_state.set(GameStates::GameOver);
+    }
+}
+
+/// Run condition of the simulation, which holds still until the actions of
+/// every player are known for the next tick.
+pub fn net_tick_ready(session: Option<Res<NetSession>>) -> bool {
+    session.is_none_or(|s| !s.is_waiting())
+}
+
+/// Swaps the local actions for everyone's.
+pub fn exchange_net_input(mut session: ResMut<NetSession>, mut inputs: ResMut<PlayerInputs>) {
+    if let Some(frame) = session.advance(inputs[0]) {
+        **inputs = frame;
+    }
+}
+
+/// Sums up the state of the simulation now and then, a peer that comes to a
+/// different sum has gone its own way.
+pub fn record_checksum(
+    mut session: ResMut<NetSession>,
+    objects: Query<&Transform, With<Physics>>,
+    scores: Query<&Score>,
+) {
+    if !session.wants_checksum() {
+        return;
+    }
+    // Added up so the order of the entities doesn't matter
+    let mut sum = 0u64;
+    for transform in &objects {
+        let mut hasher = DefaultHasher::new();
+        transform
+            .translation
+            .to_array()
+            .map(f32::to_bits)
+            .hash(&mut hasher);
+        transform
+            .rotation
+            .to_array()
+            .map(f32::to_bits)
+            .hash(&mut hasher);
+        sum = sum.wrapping_add(hasher.finish());
+    }
+    for score in &scores {
+        sum = sum.wrapping_add(**score as u64);
+    }
+    session.checksums.push(sum);
+}
+
+fn finish_net_session(mut commands: Commands, session: Option<ResMut<NetSession>>) {
+    if let Some(mut session) = session {
+        session.finish();
+        commands.remove_resource::<NetSession>();
+    }
+}
+
+/// Everyone is there once the app starts, so skip the menu.
+fn start_online_game(mut next_state: ResMut<NextState<GameStates>>) {
+    next_state.set(GameStates::Playing);
+}
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Startup,
+            start_online_game.run_if(resource_exists::<NetSession>),
+        )
+        // Before anything moves
+        .add_systems(
+            FixedUpdate,
+            poll_net_session
+                .run_if(resource_exists::<NetSession>.and(in_state(GameStates::Playing)))
+                .before(move_objects),
+        )
+        .add_systems(OnEnter(GameStates::GameOver), finish_net_session)
+        .add_systems(Last, finish_net_session.run_if(on_event::<AppExit>));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::actions::InputPlayback;
+use crate::controls::{Action, Bindings, key_label};
+use crate::helpers::despawn_recursive_query;
+use crate::net::NetSession;
+use crate::states::{GameSets, GameStates};
+use bevy::app::{App, Plugin, Update};
+use bevy::color::Color;
+use bevy::hierarchy::{BuildChildren, ChildBuild};
+use bevy::input::ButtonInput;
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::prelude::{
+    AlignItems, Commands, Component, Entity, FlexDirection, IntoSystemConfigs, JustifyContent,
+    KeyCode, NextState, Node, OnEnter, OnExit, Query, Res, ResMut, Text, TextColor, TextFont, Val,
+    With, default,
+};
+
+#[derive(Component)]
+pub struct PauseUi;
+
+pub fn pause_game(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    playback: Option<Res<InputPlayback>>,
+    net: Option<Res<NetSession>>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    // Any key ends a demo instead, and online everyone else would be kept
+    // waiting
+    if playback.is_some_and(|p| p.is_attract()) || net.is_some() {
+        return;
+    }
+    if bindings.any_just_pressed(Action::Pause, &keys, &gamepads) {
+        next_state.set(GameStates::Paused);
+    }
+}
+
+pub fn paused_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    bindings: Res<Bindings>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if bindings.any_just_pressed(Action::Pause, &keys, &gamepads) {
+        next_state.set(GameStates::Playing);
+    } else if keys.just_pressed(KeyCode::KeyQ)
+        || gamepads
+            .iter()
+            .any(|g| g.just_pressed(GamepadButton::Select))
+    {
+        next_state.set(GameStates::Menu);
+    }
+}
+
+pub fn setup_pause_ui(mut commands: Commands, bindings: Res<Bindings>) {
+    let pause = key_label(bindings.players[0].key(Action::Pause));
+    commands
+        .spawn((
+            PauseUi,
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("PAUSED"),
+                TextFont {
+                    font_size: 40.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn((
+                Text::new(format!("{pause} to resume, Q or Select to quit")),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
+pub fn cleanup_pause_ui(mut commands: Commands, ui: Query<Entity, With<PauseUi>>) {
+    despawn_recursive_query(&mut commands, ui);
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, pause_game.in_set(GameSets::Playing))
+            .add_systems(Update, paused_input.in_set(GameSets::Paused))
+            .add_systems(OnEnter(GameStates::Paused), setup_pause_ui)
+            .add_systems(OnExit(GameStates::Paused), cleanup_pause_ui);
+    }
+}
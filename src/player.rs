@@ -1,80 +1,285 @@
-use crate::bullet::Bullet;
+use crate::actions::{MAX_PLAYERS, PlayerInputs};
+use crate::asteroid::spawn_explosion;
+use crate::bullet::BulletRules;
+use crate::collision::ShipHit;
+use crate::config::GameConfig;
+use crate::difficulty::DifficultySettings;
+use crate::ghosts::GhostRendered;
 use crate::helpers::random_range;
 use crate::lifetime::Lifetime;
-use crate::states::PLAYER_SIZE;
-use crate::transforming::Physics;
+use crate::rng::GameRng;
+use crate::spatial::Collider;
+use crate::spawner::Spawner;
+use crate::states::GameStates;
+use crate::transforming::{Physics, Playfield};
+use crate::ui::PlayerLives;
 use bevy::color::Color;
-use bevy::input::ButtonInput;
-use bevy::math::{EulerRot, Vec2, Vec3};
+use bevy::math::{EulerRot, Quat, Vec2};
 use bevy::prelude::{
-    Commands, Component, KeyCode, Query, Res, Sprite, Time, Transform, Visibility, With, default,
+    Commands, Component, Entity, EventReader, EventWriter, NextState, Query, Res, ResMut, Resource,
+    Sprite, Time, Transform, Visibility, With,
 };
+use bevy_prototype_lyon::entity::Path;
+use bevy_prototype_lyon::geometry::GeometryBuilder;
+use bevy_prototype_lyon::shapes;
+use fastrand::Rng;
 use std::f32::consts::PI;
 use std::ops::Add;
 
+/// A ship in play. Ships out of lives lose this but keep their `PlayerId`,
+/// score and lives for the HUD until the game ends.
 #[derive(Component)]
 pub struct Player;
 
+/// Which player a ship belongs to, counting from 0.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayerId(pub usize);
+
+/// How many play, and against whom.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Single,
+    /// Two players against the same asteroids
+    Coop,
+    /// Two players whose bullets hit each other, the last ship flying wins
+    Versus,
+    /// Two to four players against the same asteroids, over the network
+    Online(usize),
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 3] = [GameMode::Single, GameMode::Coop, GameMode::Versus];
+
+    pub fn key(self) -> String {
+        match self {
+            GameMode::Single => "single".to_string(),
+            GameMode::Coop => "coop".to_string(),
+            GameMode::Versus => "versus".to_string(),
+            GameMode::Online(players) => format!("online{players}"),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        if let Some(players) = key.strip_prefix("online") {
+            return players
+                .parse()
+                .ok()
+                .filter(|players| (2..=MAX_PLAYERS).contains(players))
+                .map(GameMode::Online);
+        }
+        Self::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Single => "1 Player",
+            GameMode::Coop => "2P Co-op",
+            GameMode::Versus => "2P Versus",
+            GameMode::Online(_) => "Online",
+        }
+    }
+
+    pub fn players(self) -> usize {
+        match self {
+            GameMode::Single => 1,
+            GameMode::Coop | GameMode::Versus => 2,
+            GameMode::Online(players) => players,
+        }
+    }
+
+    /// Ships that have to be left flying for the game to go on.
+    fn ships_to_continue(self) -> usize {
+        match self {
+            GameMode::Versus => 2,
+            GameMode::Single | GameMode::Coop | GameMode::Online(_) => 1,
+        }
+    }
+
+    /// Where a player's ship starts and respawns, side by side around the
+    /// centre.
+    pub fn spawn_point(self, player: PlayerId) -> Vec2 {
+        let middle = (self.players() as f32 - 1.0) / 2.0;
+        Vec2::new((player.0 as f32 - middle) * 80.0, 0.0)
+    }
+}
+
+/// Versus is played with friendly fire on.
+pub fn apply_game_mode(mode: Res<GameMode>, mut rules: ResMut<BulletRules>) {
+    rules.friendly_fire = *mode == GameMode::Versus;
+}
+
+/// Size of a ship, see [`GameConfig::player_size`].
+#[derive(Component, Clone, Copy)]
+pub struct ShipSize(pub f32);
+
+impl ShipSize {
+    /// The ship triangle in local space, pointing along +x.
+    pub fn outline(self) -> [Vec2; 3] {
+        let size = self.0;
+        [
+            Vec2::new(-size / 2.0, size / 2.0),
+            Vec2::new(-size / 2.0, -size / 2.0),
+            Vec2::new(size, 0.0),
+        ]
+    }
+
+    pub fn path(self) -> Path {
+        GeometryBuilder::build_as(&shapes::Polygon {
+            points: self.outline().to_vec(),
+            closed: true,
+        })
+    }
+}
+
 #[derive(Component)]
 pub struct ShipInvincibility {
     invincibility_time: f32,
     flash_timer: f32,
+    /// Seconds left of a shield power-up, which protects without flashing
+    shield_time: f32,
+    /// The energy shield is held up
+    shield_raised: bool,
 }
 
 impl ShipInvincibility {
-    pub fn new() -> Self {
+    pub fn new(time: f32) -> Self {
         let mut s = ShipInvincibility {
             invincibility_time: 0.0,
             flash_timer: 0.0,
+            shield_time: 0.0,
+            shield_raised: false,
         };
-        s.set_invincible();
+        s.set_invincible(time);
         s
     }
     pub fn is_invincible(&self) -> bool {
-        self.invincibility_time > 0.0
+        self.invincibility_time > 0.0 || self.is_shielded()
+    }
+    /// Asteroids bounce off the ship instead of hitting it.
+    pub fn is_shielded(&self) -> bool {
+        self.shield_time > 0.0 || self.shield_raised
     }
-    pub fn set_invincible(&mut self) {
-        self.invincibility_time = 2.0;
+    pub fn shield_time(&self) -> f32 {
+        self.shield_time
+    }
+    pub fn set_shielded(&mut self, time: f32) {
+        self.shield_time = time;
+    }
+    pub fn set_shield_raised(&mut self, raised: bool) {
+        self.shield_raised = raised;
+    }
+    pub fn set_invincible(&mut self, time: f32) {
+        self.invincibility_time = time;
         self.flash_timer = 0.0;
     }
 }
 
 const FLASHING_SPEED: f32 = 0.2;
 
-fn random_orthogonal_position(origin: Vec2, direction: Vec2, range: f32) -> Vec2 {
+/// Tuning of the hyperspace jump.
+#[derive(Resource)]
+pub struct HyperspaceSettings {
+    /// Seconds between jumps, counted from the start of a jump
+    pub cooldown: f32,
+    /// Seconds the ship is gone before it reappears
+    pub jump_time: f32,
+    /// Chance from 0 to 1 that the ship blows up when it reappears
+    pub self_destruct_chance: f32,
+}
+
+impl Default for HyperspaceSettings {
+    fn default() -> Self {
+        Self {
+            cooldown: 3.0,
+            jump_time: 0.5,
+            self_destruct_chance: 0.1,
+        }
+    }
+}
+
+/// Hyperspace state of a ship. While jumping the ship is hidden, can't be
+/// controlled and doesn't collide.
+#[derive(Component, Default)]
+pub struct Hyperspace {
+    cooldown: f32,
+    jump_time: f32,
+}
+
+impl Hyperspace {
+    pub fn is_jumping(&self) -> bool {
+        self.jump_time > 0.0
+    }
+}
+
+fn random_orthogonal_position(rng: &mut Rng, origin: Vec2, direction: Vec2, range: f32) -> Vec2 {
     // Pick left (-1) or right (+1)
-    let side = if fastrand::bool() { 1.0 } else { -1.0 };
+    let side = if rng.bool() { 1.0 } else { -1.0 };
     // Get orthogonal direction and scale it randomly within the range
     let ortho = Vec2::new(-direction.y, direction.x) * side;
-    origin + ortho * random_range(0.0..range)
+    origin + ortho * random_range(rng, 0.0..range)
 }
+
+type ControlledShips<'a> = (
+    &'a mut Transform,
+    &'a mut Physics,
+    &'a mut Hyperspace,
+    &'a mut Visibility,
+    &'a ShipSize,
+    &'a PlayerId,
+);
+
 pub fn control_player(
     mut commands: Commands,
-    keys: Res<ButtonInput<KeyCode>>,
+    inputs: Res<PlayerInputs>,
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut Physics), With<Player>>,
+    settings: Res<HyperspaceSettings>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<ControlledShips, With<Player>>,
 ) {
-    for (mut transform, mut physics) in &mut query {
-        let rotation_speed = 2.0 * PI * time.delta_secs(); // Rotate 2 PI radians per second
-        if keys.pressed(KeyCode::ArrowLeft) {
-            transform.rotate_z(rotation_speed);
+    for (mut transform, mut physics, mut hyperspace, mut visibility, size, id) in &mut query {
+        let actions = inputs[id.0];
+        if hyperspace.is_jumping() {
+            continue;
         }
-        if keys.pressed(KeyCode::ArrowRight) {
-            transform.rotate_z(-rotation_speed);
+        if actions.hyperspace && hyperspace.cooldown <= 0.0 {
+            let rng = &mut rng.hyperspace;
+            let half = playfield.dims() / 2.0;
+            transform.translation.x = random_range(rng, -half.x..half.x);
+            transform.translation.y = random_range(rng, -half.y..half.y);
+            physics.reset();
+            hyperspace.jump_time = settings.jump_time;
+            hyperspace.cooldown = settings.cooldown;
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        let rotation_speed = 2.0 * PI * time.delta_secs(); // Rotate 2 PI radians per second
+        if actions.steer != 0 {
+            transform.rotate_z(-rotation_speed * f32::from(actions.steer) / 127.0);
+        } else {
+            if actions.rotate_left {
+                transform.rotate_z(rotation_speed);
+            }
+            if actions.rotate_right {
+                transform.rotate_z(-rotation_speed);
+            }
         }
 
         let angle = transform.rotation.to_euler(EulerRot::ZXY).0;
         let dir = Vec2::from_angle(angle);
-        if keys.pressed(KeyCode::KeyZ) {
+        if actions.thrust {
             physics.vel = physics.vel.add(dir * 2.0);
 
-            if fastrand::bool() {
-                let bottom_pos = transform.translation.truncate() + (-dir * PLAYER_SIZE / 2.0);
-                let rand_pos = random_orthogonal_position(bottom_pos, dir, PLAYER_SIZE / 3.0);
+            let rng = &mut rng.particles;
+            if rng.bool() {
+                let bottom_pos = transform.translation.truncate() + (-dir * size.0 / 2.0);
+                let rand_pos = random_orthogonal_position(rng, bottom_pos, dir, size.0 / 3.0);
 
                 let particle_dir = Vec2::new(-dir.x, -dir.y);
-                let speed = random_range(80.0..160.0);
-                let size = random_range(0.4..1.0);
+                let speed = random_range(rng, 80.0..160.0);
+                let size = random_range(rng, 0.4..1.0);
                 commands.spawn((
                     Transform::from_xyz(rand_pos.x, rand_pos.y, 0.0),
                     Sprite::from_color(Color::WHITE, Vec2::new(size * 2.0, size)),
@@ -83,42 +288,74 @@ pub fn control_player(
                 ));
             }
         }
+    }
+}
+
+/// Counts down the jump and the cooldown, and brings the ship back once the
+/// jump is over. An invincible ship can't blow itself up.
+pub fn update_hyperspace(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<HyperspaceSettings>,
+    mut rng: ResMut<GameRng>,
+    mut player: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Hyperspace,
+            &mut Visibility,
+            &ShipInvincibility,
+        ),
+        With<Player>,
+    >,
+    mut ship_hit: EventWriter<ShipHit>,
+) {
+    for (entity, transform, mut hyperspace, mut visibility, si) in &mut player {
+        hyperspace.cooldown = (hyperspace.cooldown - time.delta_secs()).max(0.0);
+        if !hyperspace.is_jumping() {
+            continue;
+        }
+        hyperspace.jump_time -= time.delta_secs();
+        if hyperspace.is_jumping() {
+            continue;
+        }
 
-        if keys.just_pressed(KeyCode::KeyX) {
-            let bullet_dir = dir * 250.0;
-            commands.spawn((
-                Sprite::from_color(Color::WHITE, Vec2::new(4.0, 4.0)),
-                Transform::from_translation(
-                    transform.translation + (Vec3::new(dir.x, dir.y, 0.0) * 5.0),
-                ),
-                Physics {
-                    vel: bullet_dir,
-                    max_vel: Vec2::ONE * 200.0,
-                    ..default()
-                },
-                Bullet,
-                Lifetime::new(2.0),
-            ));
+        *visibility = Visibility::Visible;
+        if !si.is_invincible() && rng.hyperspace.f32() < settings.self_destruct_chance {
+            spawn_explosion(
+                &mut commands,
+                &mut rng.particles,
+                transform.translation.truncate(),
+            );
+            ship_hit.send(ShipHit {
+                ship: entity,
+                by: None,
+            });
         }
     }
 }
 
 pub fn update_invincibility(
     time: Res<Time>,
-    mut player: Query<(&mut Visibility, &mut ShipInvincibility), With<Player>>,
+    mut player: Query<(&mut Visibility, &mut ShipInvincibility, &Hyperspace), With<Player>>,
 ) {
-    for (mut v, mut si) in &mut player {
-        if si.is_invincible() {
+    for (mut v, mut si, hyperspace) in &mut player {
+        // The ship stays hidden until it is back from hyperspace
+        if hyperspace.is_jumping() {
+            continue;
+        }
+        si.shield_time = (si.shield_time - time.delta_secs()).max(0.0);
+        if si.invincibility_time > 0.0 {
             si.invincibility_time -= time.delta_secs();
         } else {
-            return;
+            continue;
         }
 
         if si.invincibility_time < 0.0 {
             si.invincibility_time = 0.0;
             si.flash_timer = 0.0;
             *v = Visibility::Visible;
-            return;
+            continue;
         }
         si.flash_timer += time.delta_secs();
         if si.flash_timer > FLASHING_SPEED {
@@ -132,3 +369,72 @@ pub fn update_invincibility(
         }
     }
 }
+
+type LifeLosers<'a> = (
+    &'a mut Transform,
+    &'a mut Physics,
+    &'a mut ShipInvincibility,
+    &'a mut PlayerLives,
+    &'a mut Visibility,
+    &'a PlayerId,
+);
+
+pub fn lose_life(
+    mut commands: Commands,
+    difficulty: Res<DifficultySettings>,
+    mode: Res<GameMode>,
+    mut ship_hit: EventReader<ShipHit>,
+    mut players: Query<LifeLosers, With<Player>>,
+    mut spawner: Query<&mut Spawner>,
+    mut next_state: ResMut<NextState<GameStates>>,
+) {
+    if ship_hit.is_empty() {
+        return;
+    }
+    for event in ship_hit.read() {
+        let Ok((mut transform, mut physics, mut si, mut lives, mut visibility, id)) =
+            players.get_mut(event.ship)
+        else {
+            continue;
+        };
+        // Blowing up in hyperspace and crashing can land on the same tick
+        if si.is_invincible() {
+            continue;
+        }
+        if let Ok(mut spawner) = spawner.get_single_mut() {
+            spawner.reset(difficulty.spawn_interval);
+        }
+        **lives = lives.saturating_sub(1);
+        si.set_invincible(difficulty.invincibility_time);
+        transform.translation = mode.spawn_point(*id).extend(0.0);
+        transform.rotation = Quat::from_rotation_z(0.0);
+        physics.reset();
+        if **lives == 0 {
+            commands.entity(event.ship).remove::<Player>();
+            *visibility = Visibility::Hidden;
+        }
+    }
+    let flying = players
+        .iter()
+        .filter(|(.., lives, _, _)| ***lives > 0)
+        .count();
+    if flying < mode.ships_to_continue() {
+        next_state.set(GameStates::GameOver);
+    }
+}
+
+/// Applies a changed [`GameConfig::player_size`] to the ships in play.
+pub fn resize_ships(
+    config: Res<GameConfig>,
+    mut ships: Query<(&mut ShipSize, &mut Path, &mut GhostRendered, &mut Collider), With<Player>>,
+) {
+    for (mut size, mut path, mut ghost, mut collider) in &mut ships {
+        if size.0 == config.player_size {
+            continue;
+        }
+        *size = ShipSize(config.player_size);
+        *path = size.path();
+        ghost.radius = size.0;
+        collider.radius = size.0;
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::math::Vec2;
+use bevy::prelude::Transform;
+
+/// Moves an outline given in local space to where the transform puts it.
+pub fn to_world(points: &[Vec2], transform: &Transform) -> Vec<Vec2> {
+    points
+        .iter()
+        .map(|p| transform.transform_point(p.extend(0.0)).truncate())
+        .collect()
+}
+
+/// Even-odd test, works for concave outlines too.
+pub fn contains_point(polygon: &[Vec2], point: Vec2) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn project(polygon: &[Vec2], axis: Vec2) -> (f32, f32) {
+    polygon
+        .iter()
+        .map(|p| p.dot(axis))
+        .fold((f32::MAX, f32::MIN), |(min, max), d| {
+            (min.min(d), max.max(d))
+        })
+}
+
+fn has_separating_axis(a: &[Vec2], b: &[Vec2]) -> bool {
+    (0..a.len()).any(|i| {
+        let edge = a[(i + 1) % a.len()] - a[i];
+        let axis = edge.perp();
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        a_max < b_min || b_max < a_min
+    })
+}
+
+/// Separating axis test between two convex polygons.
+pub fn convex_overlap(a: &[Vec2], b: &[Vec2]) -> bool {
+    !has_separating_axis(a, b) && !has_separating_axis(b, a)
+}
+
+/// Overlap between a polygon that is star-shaped around `center`, like the
+/// asteroid outlines, and a convex polygon. The star is split into a fan of
+/// triangles around its centre, each of which is convex.
+pub fn star_overlap(center: Vec2, star: &[Vec2], convex: &[Vec2]) -> bool {
+    (0..star.len()).any(|i| {
+        let triangle = [center, star[i], star[(i + 1) % star.len()]];
+        convex_overlap(&triangle, convex)
+    })
+}
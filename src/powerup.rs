@@ -0,0 +1,298 @@
+use crate::bullet::Team;
+use crate::collision::{AsteroidDestroyed, DestroyedBy};
+use crate::helpers::get_random_vel;
+use crate::lifetime::Lifetime;
+use crate::player::{Hyperspace, Player, PlayerId, ShipInvincibility, ShipSize};
+use crate::rng::GameRng;
+use crate::spatial::{Collider, SpatialGrid};
+use crate::states::GameStates;
+use crate::transforming::{Physics, Playfield, SimId};
+use crate::ui::{PlayerLives, PowerUpUi};
+use crate::weapon::{Weapon, WeaponKind};
+use bevy::color::Color;
+use bevy::ecs::entity::EntityHashSet;
+use bevy::hierarchy::{BuildChildren, DespawnRecursiveExt};
+use bevy::math::Vec2;
+use bevy::prelude::{
+    Commands, Component, Entity, Event, EventReader, EventWriter, Query, Res, ResMut, Resource,
+    State, Text, Text2d, TextColor, TextFont, Time, Transform, Virtual, With, default,
+};
+use bevy_prototype_lyon::draw::Stroke;
+use bevy_prototype_lyon::entity::ShapeBundle;
+use bevy_prototype_lyon::geometry::GeometryBuilder;
+use bevy_prototype_lyon::shapes;
+
+/// Chance that an asteroid shot by a player leaves a power-up behind.
+const DROP_CHANCE: f32 = 0.08;
+const POWER_UP_RADIUS: f32 = 8.0;
+/// Seconds a power-up floats around before it disappears
+const POWER_UP_LIFETIME: f32 = 8.0;
+/// Seconds the timed effects last
+const SHIELD_TIME: f32 = 8.0;
+const WEAPON_TIME: f32 = 10.0;
+const TIME_SLOW_TIME: f32 = 5.0;
+/// Game speed while time is slowed
+const TIME_SLOW_SPEED: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    Shield,
+    RapidFire,
+    TripleShot,
+    Laser,
+    HomingMissile,
+    ExtraLife,
+    TimeSlow,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 7] = [
+        PowerUpKind::Shield,
+        PowerUpKind::RapidFire,
+        PowerUpKind::TripleShot,
+        PowerUpKind::Laser,
+        PowerUpKind::HomingMissile,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::TimeSlow,
+    ];
+
+    /// The weapon the power-up lends the ship for a while.
+    fn weapon(self) -> Option<WeaponKind> {
+        match self {
+            PowerUpKind::RapidFire => Some(WeaponKind::RapidFire),
+            PowerUpKind::TripleShot => Some(WeaponKind::SpreadShot),
+            PowerUpKind::Laser => Some(WeaponKind::Laser),
+            PowerUpKind::HomingMissile => Some(WeaponKind::HomingMissile),
+            _ => None,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "S",
+            PowerUpKind::RapidFire => "R",
+            PowerUpKind::TripleShot => "3",
+            PowerUpKind::Laser => "L",
+            PowerUpKind::HomingMissile => "H",
+            PowerUpKind::ExtraLife => "+",
+            PowerUpKind::TimeSlow => "T",
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+/// Sent when a ship flies into a power-up.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PowerUpCollected {
+    pub power_up: Entity,
+    pub ship: Entity,
+    pub kind: PowerUpKind,
+}
+
+/// Weapon power-up a ship is using, with the weapon it had before.
+#[derive(Component, Default)]
+pub struct PowerUpWeapon {
+    previous: Option<WeaponKind>,
+    time_left: f32,
+}
+
+/// Seconds left of slowed down time.
+#[derive(Resource, Default)]
+pub struct TimeSlow {
+    time_left: f32,
+}
+
+impl TimeSlow {
+    pub fn clear(&mut self) {
+        self.time_left = 0.0;
+    }
+}
+
+fn create_power_up(commands: &mut Commands, kind: PowerUpKind, position: Vec2, vel: Vec2) {
+    let circle = shapes::Circle {
+        radius: POWER_UP_RADIUS,
+        ..default()
+    };
+    commands
+        .spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&circle),
+                transform: Transform::from_xyz(position.x, position.y, 0.0),
+                ..default()
+            },
+            Stroke::new(Color::WHITE, 1.0),
+            Physics::new_with_vel(vel),
+            PowerUp { kind },
+            Lifetime::new(POWER_UP_LIFETIME),
+            Collider {
+                radius: POWER_UP_RADIUS,
+            },
+        ))
+        .with_child((
+            Text2d::new(kind.symbol()),
+            TextFont {
+                font_size: 10.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+}
+
+pub fn drop_power_ups(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut destroyed: EventReader<AsteroidDestroyed>,
+) {
+    let rng = &mut rng.power_ups;
+    for event in destroyed.read() {
+        let DestroyedBy::Bullet {
+            team: Team::Players,
+            ..
+        } = event.by
+        else {
+            continue;
+        };
+        if rng.f32() >= DROP_CHANCE {
+            continue;
+        }
+        let kind = PowerUpKind::ALL[rng.usize(0..PowerUpKind::ALL.len())];
+        let vel = get_random_vel(rng, 10.0..20.0);
+        create_power_up(&mut commands, kind, event.position, vel);
+    }
+}
+
+pub fn check_power_up_pickup(
+    grid: Res<SpatialGrid>,
+    playfield: Res<Playfield>,
+    players: Query<(Entity, &Transform, &Hyperspace, &ShipSize, &SimId), With<Player>>,
+    power_ups: Query<(Entity, &Transform, &PowerUp)>,
+    mut collected: EventWriter<PowerUpCollected>,
+) {
+    // Two ships touching one at once only get it once
+    let mut taken = EntityHashSet::default();
+    let players = players.iter().sort::<&SimId>();
+    for (pe, pt, _, size, _) in players.filter(|(_, _, h, ..)| !h.is_jumping()) {
+        for candidate in grid.query(pt.translation.truncate(), size.0) {
+            let Ok((ue, ut, power_up)) = power_ups.get(candidate) else {
+                continue;
+            };
+            if taken.contains(&ue) {
+                continue;
+            }
+            let delta =
+                playfield.wrapped_delta(pt.translation.truncate(), ut.translation.truncate());
+            if delta.length() <= size.0 + POWER_UP_RADIUS {
+                taken.insert(ue);
+                collected.send(PowerUpCollected {
+                    power_up: ue,
+                    ship: pe,
+                    kind: power_up.kind,
+                });
+            }
+        }
+    }
+}
+
+pub fn apply_power_ups(
+    mut commands: Commands,
+    mut collected: EventReader<PowerUpCollected>,
+    mut ships: Query<(
+        &mut ShipInvincibility,
+        &mut Weapon,
+        &mut PowerUpWeapon,
+        &mut PlayerLives,
+    )>,
+    mut time_slow: ResMut<TimeSlow>,
+) {
+    for event in collected.read() {
+        commands.entity(event.power_up).despawn_recursive();
+        let Ok((mut si, mut weapon, mut power_up_weapon, mut lives)) = ships.get_mut(event.ship)
+        else {
+            continue;
+        };
+        if let Some(kind) = event.kind.weapon() {
+            // Keep the weapon from before the first power-up
+            if power_up_weapon.previous.is_none() {
+                power_up_weapon.previous = Some(weapon.kind);
+            }
+            power_up_weapon.time_left = WEAPON_TIME;
+            *weapon = Weapon::new(kind);
+            continue;
+        }
+        match event.kind {
+            PowerUpKind::Shield => si.set_shielded(SHIELD_TIME),
+            PowerUpKind::ExtraLife => **lives += 1,
+            PowerUpKind::TimeSlow => time_slow.time_left = TIME_SLOW_TIME,
+            _ => {}
+        }
+    }
+}
+
+pub fn update_power_ups(
+    time: Res<Time>,
+    mut time_slow: ResMut<TimeSlow>,
+    mut ships: Query<(&mut Weapon, &mut PowerUpWeapon)>,
+) {
+    time_slow.time_left = (time_slow.time_left - time.delta_secs()).max(0.0);
+    for (mut weapon, mut power_up_weapon) in &mut ships {
+        let Some(previous) = power_up_weapon.previous else {
+            continue;
+        };
+        power_up_weapon.time_left -= time.delta_secs();
+        if power_up_weapon.time_left <= 0.0 {
+            *weapon = Weapon::new(previous);
+            power_up_weapon.previous = None;
+        }
+    }
+}
+
+/// Slows the whole game down while a time slow is active. Other states run at
+/// normal speed, so a game that ends slowed doesn't slow the menus.
+pub fn apply_time_slow(
+    state: Res<State<GameStates>>,
+    time_slow: Res<TimeSlow>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let in_game = matches!(state.get(), GameStates::Playing | GameStates::Paused);
+    let speed = if in_game && time_slow.time_left > 0.0 {
+        TIME_SLOW_SPEED
+    } else {
+        1.0
+    };
+    if time.relative_speed() != speed {
+        time.set_relative_speed(speed);
+    }
+}
+
+pub fn update_power_up_hud(
+    time_slow: Res<TimeSlow>,
+    ships: Query<(&PlayerId, &ShipInvincibility, &Weapon, &PowerUpWeapon), With<Player>>,
+    mut huds: Query<(&PlayerId, &mut Text), With<PowerUpUi>>,
+) {
+    for (hud_id, mut text) in &mut huds {
+        let mut active = vec![];
+        for (_, si, weapon, power_up_weapon) in ships.iter().filter(|(id, ..)| *id == hud_id) {
+            if si.shield_time() > 0.0 {
+                active.push(format!("SHIELD {:.0}", si.shield_time().ceil()));
+            }
+            if power_up_weapon.previous.is_some() {
+                let name = match weapon.kind {
+                    WeaponKind::RapidFire => "RAPID",
+                    WeaponKind::Laser => "LASER",
+                    WeaponKind::HomingMissile => "HOMING",
+                    _ => "TRIPLE",
+                };
+                active.push(format!("{name} {:.0}", power_up_weapon.time_left.ceil()));
+            }
+        }
+        // Slows down everyone
+        if time_slow.time_left > 0.0 {
+            active.push(format!("SLOW {:.0}", time_slow.time_left.ceil()));
+        }
+        text.0 = active.join("  ");
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::Resource;
+use fastrand::Rng;
+
+/// Seeded source for all gameplay randomness. Each subsystem draws from its
+/// own stream, so an extra random call in one place does not shift the
+/// sequence seen by the others.
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    /// Asked for with `--seed`, or needed to replay a recording or to stay in
+    /// step online. Every game then starts from this seed, otherwise each new
+    /// game draws a fresh one.
+    fixed: bool,
+    /// Asteroid outlines and spin
+    pub asteroids: Rng,
+    /// Velocities of fragments when an asteroid splits
+    pub splits: Rng,
+    /// Positions, sizes and velocities of newly spawned asteroids
+    pub spawns: Rng,
+    /// Thrust and explosion particles
+    pub particles: Rng,
+    /// Hyperspace destinations and self-destruction rolls
+    pub hyperspace: Rng,
+    /// Saucer kinds, entry points, course changes and aim
+    pub saucers: Rng,
+    /// Power-up drops
+    pub power_ups: Rng,
+}
+
+impl GameRng {
+    /// Plays every game with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            fixed: true,
+            asteroids: stream(seed, 1),
+            splits: stream(seed, 2),
+            spawns: stream(seed, 3),
+            particles: stream(seed, 4),
+            hyperspace: stream(seed, 5),
+            saucers: stream(seed, 6),
+            power_ups: stream(seed, 7),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets up the streams for a new game: rewinds them to the start of the
+    /// fixed seed, or draws a new seed.
+    pub fn start_game(&mut self) {
+        if self.fixed {
+            *self = Self::new(self.seed);
+        } else {
+            *self = Self::default();
+        }
+    }
+}
+
+/// A fresh seed for every game.
+impl Default for GameRng {
+    fn default() -> Self {
+        Self {
+            fixed: false,
+            ..Self::new(fastrand::u64(..))
+        }
+    }
+}
+
+fn stream(seed: u64, id: u64) -> Rng {
+    Rng::with_seed(seed ^ id.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
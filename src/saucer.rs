@@ -0,0 +1,249 @@
+use crate::asteroid::spawn_explosion;
+use crate::bullet::{Bullet, Team};
+use crate::collision::{DestroyedBy, SaucerDestroyed};
+use crate::config::GameConfig;
+use crate::ghosts::GhostRendered;
+use crate::helpers::random_range;
+use crate::lifetime::Lifetime;
+use crate::player::Player;
+use crate::rng::GameRng;
+use crate::spatial::Collider;
+use crate::transforming::{Physics, Playfield, SimId};
+use crate::ui::Score;
+use crate::weapon::Weapon;
+use bevy::color::Color;
+use bevy::math::Vec2;
+use bevy::prelude::{
+    Commands, Component, Entity, EventReader, Query, Res, ResMut, Time, Transform, With, default,
+};
+use bevy_prototype_lyon::draw::Stroke;
+use bevy_prototype_lyon::entity::ShapeBundle;
+use bevy_prototype_lyon::geometry::GeometryBuilder;
+use bevy_prototype_lyon::shapes;
+use std::f32::consts::PI;
+
+/// Largest angle in radians the small saucer misses its aim by
+const SMALL_SAUCER_SPREAD: f32 = 0.15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaucerKind {
+    /// Slow and shoots in random directions
+    Large,
+    /// Fast and aims at the ship
+    Small,
+}
+
+impl SaucerKind {
+    pub fn half_width(self) -> f32 {
+        match self {
+            SaucerKind::Large => 20.0,
+            SaucerKind::Small => 10.0,
+        }
+    }
+
+    fn speed(self) -> f32 {
+        match self {
+            SaucerKind::Large => 60.0,
+            SaucerKind::Small => 90.0,
+        }
+    }
+
+    fn fire_interval(self) -> f32 {
+        match self {
+            SaucerKind::Large => 1.2,
+            SaucerKind::Small => 1.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Saucer {
+    pub kind: SaucerKind,
+    /// Hull in local space, the part that collides
+    pub points: Vec<Vec2>,
+    turn_time: f32,
+}
+
+/// Sends a saucer across the playfield every now and then, while none is
+/// around.
+#[derive(Component)]
+pub struct SaucerSpawner {
+    next_spawn: f32,
+}
+
+impl SaucerSpawner {
+    pub fn new() -> Self {
+        Self { next_spawn: 15.0 }
+    }
+}
+
+fn create_saucer(commands: &mut Commands, kind: SaucerKind, position: Vec2, vel: Vec2, life: f32) {
+    let w = kind.half_width();
+    let points: Vec<Vec2> = [
+        (-1.0, 0.0),
+        (-0.45, -0.35),
+        (0.45, -0.35),
+        (1.0, 0.0),
+        (0.45, 0.35),
+        (-0.45, 0.35),
+    ]
+    .iter()
+    .map(|&(x, y)| Vec2::new(x, y) * w)
+    .collect();
+    let hull = shapes::Polygon {
+        points: points.clone(),
+        closed: true,
+    };
+    let dome = shapes::Polygon {
+        points: vec![
+            Vec2::new(-0.45, 0.35) * w,
+            Vec2::new(-0.25, 0.65) * w,
+            Vec2::new(0.25, 0.65) * w,
+            Vec2::new(0.45, 0.35) * w,
+        ],
+        closed: false,
+    };
+    let rim = shapes::Line(Vec2::new(-w, 0.0), Vec2::new(w, 0.0));
+
+    commands.spawn((
+        ShapeBundle {
+            path: GeometryBuilder::new()
+                .add(&hull)
+                .add(&dome)
+                .add(&rim)
+                .build(),
+            transform: Transform::from_xyz(position.x, position.y, 0.0),
+            ..default()
+        },
+        Stroke::new(Color::WHITE, 1.0),
+        Physics {
+            vel,
+            max_vel: Vec2::ONE * 200.0,
+            ..default()
+        },
+        Saucer {
+            kind,
+            points,
+            turn_time: 0.0,
+        },
+        Weapon::saucer(kind.fire_interval()),
+        // Gone once it has crossed the playfield
+        Lifetime::new(life),
+        Collider { radius: w },
+        GhostRendered { radius: w },
+    ));
+}
+
+pub fn spawn_saucers(
+    mut commands: Commands,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    scores: Query<&Score>,
+    mut rng: ResMut<GameRng>,
+    saucers: Query<(), With<Saucer>>,
+    mut spawner: Query<&mut SaucerSpawner>,
+) {
+    if !saucers.is_empty() {
+        return;
+    }
+    let Ok(mut spawner) = spawner.get_single_mut() else {
+        return;
+    };
+    spawner.next_spawn -= time.delta_secs();
+    if spawner.next_spawn > 0.0 {
+        return;
+    }
+
+    let rng = &mut rng.saucers;
+    spawner.next_spawn = random_range(rng, 15.0..25.0);
+
+    // Small saucers get more common as the best score goes up
+    let score = scores.iter().map(|s| **s).max().unwrap_or_default();
+    let small_chance = (score as f32 / 20_000.0).clamp(0.2, 0.8);
+    let kind = if rng.f32() < small_chance {
+        SaucerKind::Small
+    } else {
+        SaucerKind::Large
+    };
+    let side = if rng.bool() { 1.0 } else { -1.0 };
+    let position = Vec2::new(
+        -side * playfield.width / 2.0,
+        random_range(rng, -0.4..0.4) * playfield.height,
+    );
+    let vel = Vec2::new(side * kind.speed(), 0.0);
+    let life = playfield.width / kind.speed();
+    create_saucer(&mut commands, kind, position, vel, life);
+}
+
+/// Changes course at random intervals and fires at a steady rate.
+pub fn control_saucers(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<GameRng>,
+    players: Query<&Transform, With<Player>>,
+    mut saucers: Query<(
+        Entity,
+        &Transform,
+        &mut Physics,
+        &mut Saucer,
+        &mut Weapon,
+        &SimId,
+    )>,
+) {
+    let rng = &mut rng.saucers;
+    // Every saucer draws from the same stream, so take them in spawn order
+    for (entity, transform, mut physics, mut saucer, mut weapon, _) in
+        saucers.iter_mut().sort::<&SimId>()
+    {
+        let kind = saucer.kind;
+        let position = transform.translation.truncate();
+
+        saucer.turn_time -= time.delta_secs();
+        if saucer.turn_time <= 0.0 {
+            saucer.turn_time = random_range(rng, 0.5..1.5);
+            let climb = [-0.75, 0.0, 0.75][rng.usize(0..3)];
+            physics.vel.y = climb * kind.speed();
+        }
+
+        weapon.tick(time.delta_secs());
+        if !weapon.is_ready() {
+            continue;
+        }
+
+        // Small saucers aim at the nearest ship
+        let nearest = players
+            .iter()
+            .map(|pt| playfield.wrapped_delta(position, pt.translation.truncate()))
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+        let angle = match (kind, nearest) {
+            (SaucerKind::Small, Some(to_player)) => {
+                to_player.to_angle() + random_range(rng, -SMALL_SAUCER_SPREAD..SMALL_SAUCER_SPREAD)
+            }
+            _ => random_range(rng, 0.0..PI * 2.0),
+        };
+        let dir = Vec2::from_angle(angle);
+        // Start outside the hull so the saucer doesn't shoot itself
+        let muzzle = position + dir * (kind.half_width() + 4.0);
+        let bullet = Bullet {
+            owner: entity,
+            team: Team::Saucers,
+        };
+        weapon.fire(&mut commands, &config, bullet, muzzle, angle);
+    }
+}
+
+pub fn destroy_saucers(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    mut destroyed: EventReader<SaucerDestroyed>,
+) {
+    for event in destroyed.read() {
+        commands.entity(event.saucer).despawn();
+        if let DestroyedBy::Bullet { bullet, .. } = event.by {
+            commands.entity(bullet).despawn();
+        }
+        spawn_explosion(&mut commands, &mut rng.particles, event.position);
+    }
+}
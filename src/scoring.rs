@@ -0,0 +1,144 @@
+use crate::collision::{AsteroidDestroyed, DestroyedBy, SaucerDestroyed};
+use crate::config::GameConfig;
+use crate::player::Player;
+use crate::saucer::SaucerKind;
+use crate::transforming::Physics;
+use crate::ui::{PlayerLives, Score};
+use bevy::prelude::{Component, EventReader, Query, Res, Time, With};
+use serde::{Deserialize, Serialize};
+
+/// Points for destroying asteroids, in the spirit of the arcade original.
+/// Smaller asteroids are harder to hit and worth more. Part of [`GameConfig`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ScoreTable {
+    pub large: usize,
+    pub medium: usize,
+    pub small: usize,
+    pub large_saucer: usize,
+    pub small_saucer: usize,
+    /// Ship speed above which a hit earns `speed_bonus` on top
+    pub fast_speed: f32,
+    pub speed_bonus: usize,
+    /// Kills less than this many seconds apart build up a combo
+    pub combo_window: f32,
+    pub max_multiplier: usize,
+    /// An extra life is awarded every time the score passes a multiple of this
+    pub extra_life_every: usize,
+}
+
+impl Default for ScoreTable {
+    fn default() -> Self {
+        Self {
+            large: 20,
+            medium: 50,
+            small: 100,
+            large_saucer: 200,
+            small_saucer: 1000,
+            fast_speed: 150.0,
+            speed_bonus: 10,
+            combo_window: 1.0,
+            max_multiplier: 4,
+            extra_life_every: 10_000,
+        }
+    }
+}
+
+impl ScoreTable {
+    pub fn points_for(&self, size: f32, max_size: f32) -> usize {
+        if size > max_size / 2.0 {
+            self.large
+        } else if size > max_size / 4.0 {
+            self.medium
+        } else {
+            self.small
+        }
+    }
+
+    pub fn points_for_saucer(&self, kind: SaucerKind) -> usize {
+        match kind {
+            SaucerKind::Large => self.large_saucer,
+            SaucerKind::Small => self.small_saucer,
+        }
+    }
+}
+
+/// Combo and extra life progress of a player.
+#[derive(Component)]
+pub struct ScoringState {
+    combo: usize,
+    combo_time_left: f32,
+    next_extra_life: usize,
+}
+
+impl ScoringState {
+    pub fn new(table: &ScoreTable) -> Self {
+        Self {
+            combo: 0,
+            combo_time_left: 0.0,
+            next_extra_life: table.extra_life_every,
+        }
+    }
+
+    pub fn multiplier(&self, table: &ScoreTable) -> usize {
+        self.combo.clamp(1, table.max_multiplier)
+    }
+}
+
+/// Ends the combo once no kill came in time.
+pub fn update_combo(time: Res<Time>, mut states: Query<&mut ScoringState>) {
+    for mut state in &mut states {
+        state.combo_time_left -= time.delta_secs();
+        if state.combo_time_left <= 0.0 {
+            state.combo = 0;
+        }
+    }
+}
+
+pub fn award_score(
+    config: Res<GameConfig>,
+    mut players: Query<(&Physics, &mut Score, &mut ScoringState)>,
+    mut destroyed: EventReader<AsteroidDestroyed>,
+    mut saucers_destroyed: EventReader<SaucerDestroyed>,
+) {
+    let table = &config.score_table;
+    // Only what players shoot counts, ramming a saucer too but not an asteroid
+    let asteroid_points = destroyed
+        .read()
+        .filter(|e| matches!(e.by, DestroyedBy::Bullet { .. }))
+        .filter_map(|e| {
+            Some((
+                e.by.player()?,
+                table.points_for(e.size, config.asteroid_max_size),
+            ))
+        });
+    let saucer_points = saucers_destroyed
+        .read()
+        .filter_map(|e| Some((e.by.player()?, table.points_for_saucer(e.kind))));
+    for (shooter, mut points) in asteroid_points.chain(saucer_points) {
+        // Bullets still flying after their ship is gone for good count too
+        let Ok((physics, mut score, mut state)) = players.get_mut(shooter) else {
+            continue;
+        };
+        state.combo += 1;
+        state.combo_time_left = table.combo_window;
+
+        if physics.vel.length() > table.fast_speed {
+            points += table.speed_bonus;
+        }
+        **score += points * state.multiplier(table);
+    }
+}
+
+pub fn award_extra_lives(
+    config: Res<GameConfig>,
+    mut players: Query<(&Score, &mut ScoringState, &mut PlayerLives), With<Player>>,
+) {
+    let table = &config.score_table;
+    for (score, mut state, mut lives) in &mut players {
+        while table.extra_life_every > 0 && **score >= state.next_extra_life {
+            **lives += 1;
+            state.next_extra_life += table.extra_life_every;
+        }
+    }
+}
@@ -0,0 +1,232 @@
+use crate::actions::PlayerInputs;
+use crate::asteroid::Asteroid;
+use crate::collision::ShieldHit;
+use crate::player::{Hyperspace, PlayerId, ShipInvincibility, ShipSize};
+use crate::transforming::{Physics, Playfield};
+use bevy::color::Color;
+use bevy::hierarchy::{BuildChildren, ChildBuild, ChildBuilder, Parent};
+use bevy::math::Vec2;
+use bevy::prelude::{
+    BackgroundColor, BorderColor, Bundle, Changed, Component, EventReader, Node, Query, Res,
+    Resource, Time, Transform, UiRect, Val, Visibility, With, Without, default,
+};
+use bevy_prototype_lyon::draw::Stroke;
+use bevy_prototype_lyon::entity::{Path, ShapeBundle};
+use bevy_prototype_lyon::geometry::GeometryBuilder;
+use bevy_prototype_lyon::shapes;
+use std::f32::consts::PI;
+
+/// Energy shield mode. When enabled the ship can hold up a shield that
+/// asteroids bounce off, for as long as its energy lasts.
+#[derive(Resource)]
+pub struct ShieldSettings {
+    pub enabled: bool,
+    /// Energy used per second while the shield is up, energy goes from 0 to 1
+    pub drain: f32,
+    /// Energy regained per second while the shield is down
+    pub regen: f32,
+    /// Energy used every time an asteroid is deflected
+    pub hit_cost: f32,
+    /// Speed an asteroid moves away from the ship with after bouncing off
+    pub deflect_speed: f32,
+}
+
+impl Default for ShieldSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            drain: 0.4,
+            regen: 0.15,
+            hit_cost: 0.2,
+            deflect_speed: 40.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ShieldEnergy {
+    energy: f32,
+    /// Ran out while held, stays down until the key is let go
+    drained: bool,
+}
+
+impl Default for ShieldEnergy {
+    fn default() -> Self {
+        Self {
+            energy: 1.0,
+            drained: false,
+        }
+    }
+}
+
+/// Circle drawn around a shielded ship.
+#[derive(Component)]
+pub struct ShieldRing;
+
+#[derive(Component)]
+pub struct ShieldBarUi;
+
+#[derive(Component)]
+pub struct ShieldBarFill;
+
+pub fn shield_radius(size: ShipSize) -> f32 {
+    size.0 * 1.5
+}
+
+/// The shield circle as a polygon, for the overlap tests.
+pub fn shield_outline(center: Vec2, radius: f32) -> Vec<Vec2> {
+    (0..12)
+        .map(|i| center + Vec2::from_angle(i as f32 * PI / 6.0) * radius)
+        .collect()
+}
+
+fn ring_path(size: ShipSize) -> Path {
+    GeometryBuilder::build_as(&shapes::Circle {
+        radius: shield_radius(size),
+        ..default()
+    })
+}
+
+/// Child of the ship, hidden until the shield is up.
+pub fn shield_ring(size: ShipSize) -> impl Bundle {
+    (
+        ShapeBundle {
+            path: ring_path(size),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Stroke::new(Color::WHITE, 1.0),
+        ShieldRing,
+    )
+}
+
+/// The energy bar of one player, placed in their HUD panel.
+pub fn spawn_shield_bar(parent: &mut ChildBuilder, player: PlayerId) {
+    parent
+        .spawn((
+            ShieldBarUi,
+            player,
+            Node {
+                margin: UiRect::vertical(Val::Px(4.0)),
+                width: Val::Px(100.0),
+                height: Val::Px(8.0),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BorderColor(Color::WHITE),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ShieldBarFill,
+                player,
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                BackgroundColor(Color::WHITE),
+            ));
+        });
+}
+
+pub fn update_shield_energy(
+    inputs: Res<PlayerInputs>,
+    time: Res<Time>,
+    settings: Res<ShieldSettings>,
+    mut ships: Query<(
+        &mut ShipInvincibility,
+        &mut ShieldEnergy,
+        &Hyperspace,
+        &PlayerId,
+    )>,
+) {
+    for (mut si, mut shield, hyperspace, id) in &mut ships {
+        let actions = inputs[id.0];
+        if !actions.shield {
+            shield.drained = false;
+        }
+        let raised = settings.enabled
+            && actions.shield
+            && !shield.drained
+            && shield.energy > 0.0
+            && !hyperspace.is_jumping();
+        if raised {
+            shield.energy = (shield.energy - settings.drain * time.delta_secs()).max(0.0);
+            shield.drained = shield.energy <= 0.0;
+        } else {
+            shield.energy = (shield.energy + settings.regen * time.delta_secs()).min(1.0);
+        }
+        si.set_shield_raised(raised);
+    }
+}
+
+type ShieldlessAsteroids = (With<Asteroid>, Without<ShieldEnergy>);
+
+/// Pushes asteroids away from the shield. Only the energy shield pays for it,
+/// the shield power-up doesn't run out early.
+pub fn deflect_asteroids(
+    settings: Res<ShieldSettings>,
+    playfield: Res<Playfield>,
+    mut shield_hit: EventReader<ShieldHit>,
+    mut ships: Query<(&Transform, &ShipInvincibility, &mut ShieldEnergy)>,
+    mut asteroids: Query<(&Transform, &mut Physics), ShieldlessAsteroids>,
+) {
+    for event in shield_hit.read() {
+        let Ok((st, si, mut shield)) = ships.get_mut(event.ship) else {
+            continue;
+        };
+        let Ok((at, mut physics)) = asteroids.get_mut(event.asteroid) else {
+            continue;
+        };
+        let away = playfield
+            .wrapped_delta(st.translation.truncate(), at.translation.truncate())
+            .normalize_or(Vec2::X);
+        let along = physics.vel.dot(away);
+        if along >= settings.deflect_speed {
+            continue;
+        }
+        physics.vel += away * (settings.deflect_speed - along);
+        // Still overlapping but already on its way out costs nothing
+        if along < 0.0 && si.shield_time() <= 0.0 {
+            shield.energy = (shield.energy - settings.hit_cost).max(0.0);
+            shield.drained = shield.energy <= 0.0;
+        }
+    }
+}
+
+pub fn update_shield_rings(
+    ships: Query<&ShipInvincibility>,
+    mut rings: Query<(&Parent, &mut Visibility), With<ShieldRing>>,
+) {
+    for (parent, mut visibility) in &mut rings {
+        let shielded = ships.get(parent.get()).is_ok_and(|si| si.is_shielded());
+        *visibility = if shielded {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+/// Keeps the rings the size of their ship.
+pub fn resize_shield_rings(
+    ships: Query<&ShipSize, Changed<ShipSize>>,
+    mut rings: Query<(&Parent, &mut Path), With<ShieldRing>>,
+) {
+    for (parent, mut path) in &mut rings {
+        if let Ok(size) = ships.get(parent.get()) {
+            *path = ring_path(*size);
+        }
+    }
+}
+
+pub fn update_shield_bar(
+    ships: Query<(&PlayerId, &ShieldEnergy)>,
+    mut fills: Query<(&PlayerId, &mut Node), With<ShieldBarFill>>,
+) {
+    for (id, shield) in &ships {
+        for (_, mut node) in fills.iter_mut().filter(|(i, _)| *i == id) {
+            node.width = Val::Percent(shield.energy * 100.0);
+        }
+    }
+}
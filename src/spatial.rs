@@ -0,0 +1,90 @@
+use crate::transforming::{Playfield, SimId};
+use bevy::math::Vec2;
+use bevy::prelude::{Component, Entity, Query, Res, ResMut, Resource, Transform};
+
+const CELL_SIZE: f32 = 64.0;
+
+/// Bounding circle of an entity that takes part in collision checks.
+#[derive(Component, Clone, Copy)]
+pub struct Collider {
+    pub radius: f32,
+}
+
+/// Uniform grid over the playfield used as a collision broadphase. Cells wrap
+/// around the edges the same way `wrap_objects` does, so an object near one
+/// edge is also found from the opposite side.
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cols: i32,
+    rows: i32,
+    origin: Vec2,
+    cells: Vec<Vec<(SimId, Entity)>>,
+}
+
+impl SpatialGrid {
+    fn resize(&mut self, playfield: &Playfield) {
+        self.cols = (playfield.width / CELL_SIZE).ceil().max(1.0) as i32;
+        self.rows = (playfield.height / CELL_SIZE).ceil().max(1.0) as i32;
+        self.origin = -playfield.dims() / 2.0;
+        self.cells.resize((self.cols * self.rows) as usize, vec![]);
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+    }
+
+    /// Indices of every cell touched by the circle, each at most once.
+    fn covered_cells(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let min = ((pos - radius - self.origin) / CELL_SIZE).floor();
+        let max = ((pos + radius - self.origin) / CELL_SIZE).floor();
+        // Never visit a column or row twice when the circle is wider than the grid
+        let x_count = (max.x - min.x + 1.0).min(self.cols as f32) as i32;
+        let y_count = (max.y - min.y + 1.0).min(self.rows as f32) as i32;
+        let (min_x, min_y) = (min.x as i32, min.y as i32);
+
+        (0..y_count).flat_map(move |dy| {
+            (0..x_count).map(move |dx| {
+                let x = (min_x + dx).rem_euclid(self.cols);
+                let y = (min_y + dy).rem_euclid(self.rows);
+                (y * self.cols + x) as usize
+            })
+        })
+    }
+
+    pub fn insert(&mut self, entity: Entity, id: SimId, pos: Vec2, radius: f32) {
+        let cells: Vec<usize> = self.covered_cells(pos, radius).collect();
+        for cell in cells {
+            self.cells[cell].push((id, entity));
+        }
+    }
+
+    /// Entities whose cells overlap the given circle. This is a superset of
+    /// the actual hits, in spawn order so the result is the same on every peer.
+    pub fn query(&self, pos: Vec2, radius: f32) -> Vec<Entity> {
+        if self.cells.is_empty() {
+            return vec![];
+        }
+        let mut found: Vec<(SimId, Entity)> = self
+            .covered_cells(pos, radius)
+            .flat_map(|cell| self.cells[cell].iter().copied())
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found.into_iter().map(|(_, entity)| entity).collect()
+    }
+}
+
+pub fn update_spatial_grid(
+    playfield: Res<Playfield>,
+    mut grid: ResMut<SpatialGrid>,
+    colliders: Query<(Entity, &SimId, &Transform, &Collider)>,
+) {
+    grid.resize(&playfield);
+    for (entity, id, transform, collider) in &colliders {
+        grid.insert(
+            entity,
+            *id,
+            transform.translation.truncate(),
+            collider.radius,
+        );
+    }
+}
@@ -1,8 +1,11 @@
 use crate::asteroid;
+use crate::difficulty::DifficultySettings;
 use crate::helpers::random_range;
 use crate::player::Player;
+use crate::rng::GameRng;
+use crate::transforming::Playfield;
 use bevy::math::{Vec2, Vec3};
-use bevy::prelude::{Commands, Component, Query, Res, Time, Transform, Window, With};
+use bevy::prelude::{Commands, Component, Query, Res, ResMut, Time, Transform, With};
 use std::f32::consts::PI;
 
 #[derive(Component)]
@@ -13,32 +16,34 @@ pub struct Spawner {
 }
 
 impl Spawner {
-    pub fn new() -> Self {
+    pub fn new(interval: f32) -> Self {
         let mut s = Self {
             next_spawn: 0.0,
             next_spawn_time: 0.0,
             spawned_count: 0,
         };
-        s.reset();
+        s.reset(interval);
         s
     }
 
-    pub fn reset(&mut self) {
-        self.next_spawn_time = 6.0;
+    pub fn reset(&mut self, interval: f32) {
+        self.next_spawn_time = interval;
         self.next_spawn = 0.0;
     }
 }
 
 pub fn spawn_objects(
     mut commands: Commands,
-    window: Query<&Window>,
-    player: Query<&Transform, With<Player>>,
+    difficulty: Res<DifficultySettings>,
+    playfield: Res<Playfield>,
+    mut rng: ResMut<GameRng>,
+    players: Query<&Transform, With<Player>>,
     time: Res<Time>,
     mut query: Query<&mut Spawner>,
 ) {
-    let Ok(pt) = player.get_single() else {
+    if players.is_empty() {
         return;
-    };
+    }
     let mut spawner = query.single_mut();
     spawner.next_spawn -= time.delta_secs();
     if spawner.next_spawn > 0.0 {
@@ -47,24 +52,27 @@ pub fn spawn_objects(
 
     spawner.spawned_count += 1;
     if spawner.spawned_count % 10 == 0 {
-        spawner.next_spawn_time *= 0.9;
+        spawner.next_spawn_time *= difficulty.spawn_ramp;
     }
 
     spawner.next_spawn = spawner.next_spawn_time;
 
-    let window = window.single();
+    let dims = playfield.dims();
+    let rng = &mut *rng;
     loop {
-        let dims = Vec2::new(window.width(), window.height());
-
-        let angle = random_range(0.0..PI * 2.0);
+        let angle = random_range(&mut rng.spawns, 0.0..PI * 2.0);
         let dir = Vec2::from_angle(angle);
 
         let pos = (dir * dims) - dims / 2.0;
-        let vel = Vec2::from_angle(random_range(0.0..PI * 2.0)) * random_range(10.0..20.0);
+        let vel = Vec2::from_angle(random_range(&mut rng.spawns, 0.0..PI * 2.0))
+            * random_range(&mut rng.spawns, difficulty.asteroid_speed.clone());
 
-        let size = random_range(40.0..120.0);
-        if pt.translation.distance(Vec3::new(pos.x, pos.y, 0.0)) > size * 1.5 {
-            asteroid::create_asteroid(&mut commands, size, pos, vel);
+        let size = random_range(&mut rng.spawns, 40.0..120.0);
+        let clear = players
+            .iter()
+            .all(|pt| pt.translation.distance(Vec3::new(pos.x, pos.y, 0.0)) > size * 1.5);
+        if clear {
+            asteroid::create_asteroid(&mut commands, &mut rng.asteroids, size, pos, vel);
             return;
         }
     }
@@ -1,32 +1,73 @@
+use crate::actions::{MAX_PLAYERS, gather_local_input, read_player_input, record_player_input};
 use crate::asteroid::Asteroid;
-use crate::bullet::Bullet;
-use crate::collision::{check_bullet_collision, check_player_collision};
+use crate::asteroid::{spawn_explosions, split_asteroids};
+use crate::bullet::{Bullet, BulletRules};
+use crate::collision::{
+    AsteroidDestroyed, ConsumedThisTick, SaucerDestroyed, ShieldHit, ShipHit,
+    check_bullet_collision, check_bullet_player_collision, check_bullet_saucer_collision,
+    check_player_collision, check_player_saucer_collision, check_saucer_asteroid_collision,
+    check_shield_collision, clear_consumed, despawn_destroyed,
+};
+use crate::config::{ConfigPlugin, GameConfig};
+use crate::difficulty::{Difficulty, DifficultySettings, DifficultyUi, update_difficulty_settings};
+use crate::ghosts::GhostRendered;
 use crate::helpers::{despawn_query, despawn_recursive_query};
 use crate::lifetime::check_lifetime;
-use crate::player::{Player, ShipInvincibility, update_invincibility};
+use crate::net::{NetSession, exchange_net_input, record_checksum};
+use crate::player::{
+    GameMode, Hyperspace, HyperspaceSettings, Player, PlayerId, ShipInvincibility, ShipSize,
+    lose_life, resize_ships, update_hyperspace, update_invincibility,
+};
+use crate::powerup::{
+    PowerUp, PowerUpCollected, PowerUpWeapon, TimeSlow, apply_power_ups, apply_time_slow,
+    check_power_up_pickup, drop_power_ups, update_power_up_hud, update_power_ups,
+};
+use crate::rng::GameRng;
+use crate::saucer::{Saucer, SaucerSpawner, control_saucers, destroy_saucers, spawn_saucers};
+use crate::scoring::{ScoringState, award_extra_lives, award_score, update_combo};
+use crate::shield::{
+    ShieldEnergy, ShieldSettings, deflect_asteroids, resize_shield_rings, shield_ring,
+    spawn_shield_bar, update_shield_bar, update_shield_energy, update_shield_rings,
+};
+use crate::spatial::{Collider, SpatialGrid, update_spatial_grid};
 use crate::spawner::Spawner;
-use crate::transforming::Physics;
-use crate::ui::{LivesUi, MenuButtonText, MenuUi, PlayerLives, Score, ScoreUi, update_scoreboard};
+use crate::transforming::{
+    Physics, Playfield, SimIds, interpolate_transforms, restore_simulated_transforms,
+    store_previous_transforms,
+};
+use crate::ui::{
+    HudUi, LivesUi, MenuUi, PlayerLives, PowerUpUi, Score, ScoreUi, update_scoreboard, white_text,
+};
+use crate::weapon::{Weapon, WeaponKind, fire_weapons, steer_homing};
 use crate::{player, spawner};
 use bevy::DefaultPlugins;
 use bevy::app::{App, Plugin, Startup, Update};
 use bevy::color::Color;
 use bevy::hierarchy::{BuildChildren, ChildBuild, DespawnRecursiveExt};
-use bevy::math::Vec2;
+use bevy::input::InputSystem;
 use bevy::prelude::*;
 use bevy_prototype_lyon::draw::Stroke;
 use bevy_prototype_lyon::entity::ShapeBundle;
-use bevy_prototype_lyon::geometry::GeometryBuilder;
 use bevy_prototype_lyon::plugin::ShapePlugin;
-use bevy_prototype_lyon::shapes;
 
-pub const PLAYER_SIZE: f32 = 16.0;
+/// Rate of the fixed timestep all gameplay systems run at.
+pub const TICK_RATE: f64 = 60.0;
+
+/// Outline colour of each player's ship.
+const SHIP_COLORS: [Color; MAX_PLAYERS] = [
+    Color::WHITE,
+    Color::srgb(0.6, 0.9, 1.0),
+    Color::srgb(1.0, 0.85, 0.5),
+    Color::srgb(0.7, 1.0, 0.6),
+];
 
 #[derive(SystemSet, Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub enum GameSets {
     #[default]
     Menu,
     Playing,
+    Paused,
+    GameOver,
 }
 
 #[derive(States, Debug, Default, Clone, PartialEq, Eq, Hash)]
@@ -34,94 +75,248 @@ pub enum GameStates {
     #[default]
     Menu,
     Playing,
+    Paused,
+    GameOver,
 }
 
 pub fn setup_playing_state(
     mut commands: Commands,
-    mut score: ResMut<Score>,
-    mut player_lives: ResMut<PlayerLives>,
+    config: Res<GameConfig>,
+    mode: Res<GameMode>,
+    difficulty: Res<DifficultySettings>,
+    mut rng: ResMut<GameRng>,
+    mut time_slow: ResMut<TimeSlow>,
 ) {
-    let player_size = PLAYER_SIZE / 2.0;
-    let player_triangle = shapes::Polygon {
-        points: vec![
-            Vec2::new(-player_size, player_size),
-            Vec2::new(-player_size, -player_size),
-            Vec2::new(player_size * 2.0, 0.0),
-        ],
-        closed: true,
-    };
+    // Every game with the same seed starts from the same random sequence
+    rng.start_game();
+    info!("Starting game with seed {}", rng.seed());
+
+    let size = ShipSize(config.player_size);
+    for (i, color) in SHIP_COLORS.into_iter().enumerate().take(mode.players()) {
+        let id = PlayerId(i);
+        commands
+            .spawn((
+                ShapeBundle {
+                    path: size.path(),
+                    transform: Transform::from_translation(mode.spawn_point(id).extend(0.0)),
+                    ..default()
+                },
+                Stroke::new(color, 1.0),
+                Physics::new(),
+                Player,
+                id,
+                size,
+                ShipInvincibility::new(difficulty.invincibility_time),
+                Hyperspace::default(),
+                Weapon::new(WeaponKind::Blaster),
+                PowerUpWeapon::default(),
+                ShieldEnergy::default(),
+                GhostRendered { radius: size.0 },
+            ))
+            .insert((
+                Collider { radius: size.0 },
+                PlayerLives::new(difficulty.lives),
+                Score::default(),
+                ScoringState::new(&config.score_table),
+            ))
+            .with_child(shield_ring(size));
+    }
 
+    time_slow.clear();
     commands.spawn((
-        ShapeBundle {
-            path: GeometryBuilder::build_as(&player_triangle),
-            transform: Transform::from_xyz(0.0, 0.0, 0.0),
-            ..default()
-        },
-        Stroke::new(Color::WHITE, 1.0),
-        Physics::new(),
-        Player,
-        ShipInvincibility::new(),
+        Spawner::new(difficulty.spawn_interval),
+        SaucerSpawner::new(),
     ));
+}
 
-    player_lives.reset();
-    score.clear();
+pub fn spawn_hud(
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    shield: Res<ShieldSettings>,
+) {
+    let players = mode.players();
     commands
         .spawn((
-            Text::new("Lives: "),
-            TextFont {
-                font_size: 24.0,
-                ..default()
-            },
-            TextColor(Color::WHITE),
-            LivesUi,
+            HudUi,
             Node {
                 position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(64.0),
                 ..default()
             },
         ))
-        .with_child((
-            TextSpan::default(),
-            TextFont {
-                font_size: 24.0,
-                ..default()
-            },
-            TextColor(Color::WHITE),
-        ));
+        .with_children(|parent| {
+            for i in 0..players {
+                parent.spawn((white_text("", 32.0), ScoreUi, PlayerId(i)));
+            }
+        });
+
+    for i in 0..players {
+        let id = PlayerId(i);
+        // Players one and two in the top corners, three and four in the
+        // bottom ones above the difficulty
+        let (left, right, align_items) = if i % 2 == 0 {
+            (Val::Px(4.0), Val::Auto, AlignItems::FlexStart)
+        } else {
+            (Val::Auto, Val::Px(8.0), AlignItems::FlexEnd)
+        };
+        let (top, bottom) = if i < 2 {
+            (Val::Px(0.0), Val::Auto)
+        } else {
+            (Val::Auto, Val::Px(24.0))
+        };
+        let label = if players == 1 {
+            "Lives: ".to_string()
+        } else {
+            format!("P{} Lives: ", i + 1)
+        };
+        commands
+            .spawn((
+                HudUi,
+                Node {
+                    position_type: PositionType::Absolute,
+                    top,
+                    bottom,
+                    left,
+                    right,
+                    flex_direction: FlexDirection::Column,
+                    align_items,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((white_text(label, 24.0), LivesUi, id))
+                    .with_child((
+                        TextSpan::default(),
+                        TextFont {
+                            font_size: 24.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                if shield.enabled {
+                    spawn_shield_bar(parent, id);
+                }
+                parent.spawn((white_text("", 16.0), PowerUpUi, id));
+            });
+    }
+
     commands.spawn((
-        Text::new(""),
-        TextFont {
-            font_size: 32.0,
-            ..default()
-        },
-        TextColor(Color::WHITE),
-        ScoreUi,
+        HudUi,
+        white_text(difficulty.name().to_uppercase(), 16.0),
+        DifficultyUi,
         Node {
-            position_type: PositionType::Relative,
-            flex_direction: FlexDirection::Column,
-            justify_self: JustifySelf::Center,
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(4.0),
+            left: Val::Px(8.0),
             ..default()
         },
     ));
-    commands.spawn(Spawner::new());
 }
 
 pub struct PlayingPlugin;
 
 impl Plugin for PlayingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                player::control_player,
-                spawner::spawn_objects,
-                check_lifetime,
-                check_bullet_collision,
-                check_player_collision,
-                update_invincibility,
-                update_scoreboard,
+        app.add_event::<AsteroidDestroyed>()
+            .add_event::<ShipHit>()
+            .add_event::<SaucerDestroyed>()
+            .add_event::<PowerUpCollected>()
+            .add_event::<ShieldHit>()
+            .init_resource::<ConsumedThisTick>()
+            .init_resource::<HyperspaceSettings>()
+            .init_resource::<BulletRules>()
+            .init_resource::<TimeSlow>()
+            .init_resource::<ShieldSettings>()
+            .init_resource::<Difficulty>()
+            .init_resource::<DifficultySettings>()
+            .init_resource::<GameMode>()
+            .add_systems(
+                PreUpdate,
+                gather_local_input
+                    .after(InputSystem)
+                    .run_if(in_state(GameStates::Playing)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        read_player_input,
+                        exchange_net_input.run_if(resource_exists::<NetSession>),
+                        record_player_input,
+                        player::control_player,
+                        fire_weapons,
+                        update_hyperspace,
+                        update_shield_energy,
+                    )
+                        .chain(),
+                    spawner::spawn_objects,
+                    (spawn_saucers, control_saucers).chain(),
+                    steer_homing,
+                    check_lifetime,
+                    update_spatial_grid,
+                    clear_consumed,
+                    check_bullet_collision,
+                    check_shield_collision,
+                    check_player_collision,
+                    (
+                        check_bullet_saucer_collision,
+                        check_saucer_asteroid_collision,
+                        check_player_saucer_collision,
+                        check_bullet_player_collision,
+                        check_power_up_pickup,
+                    )
+                        .chain(),
+                    // Outcomes of the collisions above
+                    (
+                        despawn_destroyed,
+                        destroy_saucers,
+                        update_combo,
+                        award_score,
+                        award_extra_lives,
+                        split_asteroids,
+                        spawn_explosions,
+                        deflect_asteroids,
+                        drop_power_ups,
+                        apply_power_ups,
+                        lose_life,
+                    )
+                        .chain(),
+                    update_invincibility,
+                    update_power_ups,
+                    record_checksum.run_if(resource_exists::<NetSession>),
+                )
+                    // A fixed order keeps the simulation reproducible
+                    .chain()
+                    .in_set(GameSets::Playing),
             )
-                .in_set(GameSets::Playing),
-        );
+            .add_systems(
+                Update,
+                (
+                    update_scoreboard,
+                    update_power_up_hud,
+                    update_shield_rings,
+                    update_shield_bar,
+                )
+                    .in_set(GameSets::Playing),
+            )
+            .add_systems(
+                Update,
+                (
+                    resize_ships.run_if(resource_changed::<GameConfig>),
+                    resize_shield_rings,
+                )
+                    .chain()
+                    .in_set(GameSets::Playing),
+            )
+            .add_systems(Update, apply_time_slow)
+            .add_systems(
+                Update,
+                update_difficulty_settings.run_if(resource_changed::<GameConfig>),
+            );
     }
 }
 
@@ -129,88 +324,71 @@ pub fn setup_cam(mut commands: Commands) {
     commands.spawn(Camera2d);
 }
 
+/// Resources and state shared by the windowed and the headless builds.
+pub struct GameResourcesPlugin;
+
+impl Plugin for GameResourcesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            .add_plugins(ConfigPlugin);
+        app.init_resource::<Playfield>()
+            .init_resource::<GameRng>()
+            .init_resource::<SpatialGrid>()
+            .init_resource::<SimIds>();
+
+        // Rendered transforms are blended between fixed ticks
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                restore_simulated_transforms.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                interpolate_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            ),
+        )
+        .add_systems(FixedFirst, store_previous_transforms);
+    }
+}
+
 pub struct InitPlugin;
 
 impl Plugin for InitPlugin {
     fn build(&self, app: &mut App) {
+        let playfield = Playfield::default();
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                resolution: (640.0, 480.0).into(),
+                resolution: (playfield.width, playfield.height).into(),
                 resizable: false,
                 ..default()
             }),
             ..default()
         }))
         .insert_state(GameStates::Menu)
-        .add_plugins(ShapePlugin)
-        .insert_resource(Score::default())
-        .insert_resource(PlayerLives::default())
+        .add_plugins((ShapePlugin, GameResourcesPlugin))
+        .insert_resource(playfield)
         .insert_resource(ClearColor(Color::BLACK))
         .add_systems(Startup, setup_cam);
     }
 }
 
-pub fn setup_menu(mut commands: Commands) {
-    commands
-        .spawn((
-            MenuUi,
-            Node {
-                // center button
-                width: Val::Percent(100.),
-                height: Val::Percent(100.),
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-        ))
-        .with_children(|parent| {
-            parent
-                .spawn((
-                    Button,
-                    Node {
-                        width: Val::Percent(20.),
-                        height: Val::Percent(6.),
-                        // horizontally center child text
-                        justify_content: JustifyContent::Center,
-                        // vertically center child text
-                        align_items: AlignItems::Center,
-                        ..default()
-                    },
-                    BackgroundColor(Color::WHITE),
-                ))
-                .with_children(|parent| {
-                    parent.spawn((
-                        MenuButtonText,
-                        Text::new("Play"),
-                        TextFont {
-                            font_size: 24.0,
-                            ..default()
-                        },
-                        TextColor::BLACK,
-                    ));
-                });
-        });
-}
-
 pub fn cleanup_menu(mut commands: Commands, menu_data: Query<Entity, With<MenuUi>>) {
     for e in &menu_data {
         commands.entity(e).despawn_recursive();
     }
 }
 
+type Objects = Or<(With<Asteroid>, With<Saucer>, With<PowerUp>)>;
+
 pub fn cleanup_playing(
     mut commands: Commands,
-    player: Query<Entity, With<Player>>,
-    score_ui: Query<Entity, With<ScoreUi>>,
-    lives_ui: Query<Entity, With<LivesUi>>,
+    // Ships out of lives are no longer a `Player`
+    ships: Query<Entity, With<PlayerId>>,
+    hud: Query<Entity, With<HudUi>>,
     spawner: Query<Entity, With<Spawner>>,
-    asteroids: Query<Entity, With<Asteroid>>,
+    objects: Query<Entity, Objects>,
     bullets: Query<Entity, With<Bullet>>,
 ) {
-    despawn_recursive_query(&mut commands, score_ui);
-    despawn_recursive_query(&mut commands, lives_ui);
-    despawn_query(&mut commands, player);
+    despawn_recursive_query(&mut commands, hud);
+    despawn_recursive_query(&mut commands, ships);
     despawn_query(&mut commands, spawner);
     despawn_query(&mut commands, bullets);
-    despawn_query(&mut commands, asteroids);
+    despawn_recursive_query(&mut commands, objects);
 }
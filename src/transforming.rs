@@ -1,8 +1,73 @@
+use bevy::ecs::component::ComponentId;
+use bevy::ecs::world::DeferredWorld;
 use bevy::math::{Quat, Vec2, Vec3};
-use bevy::prelude::{Component, Query, Res, Time, Transform, Window, With};
+use bevy::prelude::{Component, Entity, Fixed, Query, Res, Resource, Time, Transform, With};
 use std::ops::Add;
 
+/// Logical size of the play area. Used instead of the primary `Window` so the
+/// simulation can run without a display.
+#[derive(Resource, Clone, Copy)]
+pub struct Playfield {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Playfield {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn dims(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    /// Shortest offset from `from` to `to` when the edges wrap around.
+    pub fn wrapped_delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let dims = self.dims();
+        let delta = to - from;
+        delta - (delta / dims).round() * dims
+    }
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self::new(640.0, 480.0)
+    }
+}
+
+/// Simulated transform at the end of the last two fixed ticks. Between ticks
+/// the rendered `Transform` is blended from these so motion stays smooth at
+/// any frame rate.
 #[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+/// Spawn order of a simulated object. Ghosts and the UI share entity ids with
+/// the simulation and differ between builds, so collisions are resolved in
+/// this order instead of by `Entity` or query order.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[component(on_add = assign_sim_id)]
+pub struct SimId(u64);
+
+/// Hands out the next `SimId`. Only the order matters, so it never restarts.
+#[derive(Resource, Default)]
+pub struct SimIds {
+    next: u64,
+}
+
+fn assign_sim_id(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let mut ids = world.resource_mut::<SimIds>();
+    let id = SimId(ids.next);
+    ids.next += 1;
+    if let Some(mut sim_id) = world.get_mut::<SimId>(entity) {
+        *sim_id = id;
+    }
+}
+
+#[derive(Component, Default)]
+#[require(Interpolated, SimId)]
 pub struct Physics {
     pub acc: Vec2,
     pub vel: Vec2,
@@ -46,21 +111,58 @@ pub fn move_objects(time: Res<Time>, mut query: Query<(&mut Transform, &mut Phys
     }
 }
 
-pub fn wrap_objects(window: Query<&Window>, mut query: Query<&mut Transform, With<Physics>>) {
-    let window = window.single();
-
+pub fn wrap_objects(playfield: Res<Playfield>, mut query: Query<&mut Transform, With<Physics>>) {
     for mut transform in &mut query {
-        if transform.translation.y > window.height() / 2.0 {
-            transform.translation.y -= window.height();
+        if transform.translation.y > playfield.height / 2.0 {
+            transform.translation.y -= playfield.height;
+        }
+        if transform.translation.y < -playfield.height / 2.0 {
+            transform.translation.y += playfield.height;
         }
-        if transform.translation.y < -window.height() / 2.0 {
-            transform.translation.y += window.height();
+        if transform.translation.x > playfield.width / 2.0 {
+            transform.translation.x -= playfield.width;
         }
-        if transform.translation.x > window.width() / 2.0 {
-            transform.translation.x -= window.width();
+        if transform.translation.x < -playfield.width / 2.0 {
+            transform.translation.x += playfield.width;
+        }
+    }
+}
+
+/// Puts back the simulated transform before the fixed ticks of this frame run.
+pub fn restore_simulated_transforms(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in &mut query {
+        if let Some(current) = interpolated.current {
+            *transform = current;
         }
-        if transform.translation.x < -window.width() / 2.0 {
-            transform.translation.x += window.width();
+    }
+}
+
+pub fn store_previous_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.previous = Some(*transform);
+    }
+}
+
+pub fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    playfield: Res<Playfield>,
+    mut query: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    for (mut transform, mut interpolated) in &mut query {
+        let current = *transform;
+        interpolated.current = Some(current);
+        let Some(previous) = interpolated.previous else {
+            continue;
+        };
+        // Don't blend across the playfield when the object was wrapped
+        let jump = (current.translation - previous.translation)
+            .truncate()
+            .abs();
+        if jump.x > playfield.width / 2.0 || jump.y > playfield.height / 2.0 {
+            continue;
         }
+        transform.translation = previous.translation.lerp(current.translation, alpha);
+        transform.rotation = previous.rotation.slerp(current.rotation, alpha);
     }
 }
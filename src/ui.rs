@@ -1,71 +1,112 @@
-use crate::GameStates;
-use bevy::hierarchy::Children;
+use crate::player::PlayerId;
+use bevy::color::Color;
+use bevy::hierarchy::{BuildChildren, ChildBuild, ChildBuilder, Children};
 use bevy::prelude::{
-    Button, Changed, Component, Deref, DerefMut, Entity, Interaction, NextState, Query, Res,
-    ResMut, Resource, Single, Text, TextFont, TextUiWriter, With,
+    AlignItems, BackgroundColor, Bundle, Button, Component, Deref, DerefMut, EntityCommands,
+    JustifyContent, Node, Query, Text, TextColor, TextFont, TextSpan, Val, With, default,
 };
 #[derive(Component)]
 pub struct MenuButtonText;
 
-#[derive(Resource, Deref, DerefMut, Default)]
+#[derive(Component, Deref, DerefMut, Default)]
 pub struct Score(usize);
 
-impl Score {
-    pub fn clear(&mut self) {
-        self.0 = 0
-    }
-}
-#[derive(Resource, Deref, DerefMut, Default)]
+#[derive(Component, Deref, DerefMut)]
 pub struct PlayerLives(usize);
 
 impl PlayerLives {
-    pub fn reset(&mut self) {
-        self.0 = 3
+    pub fn new(lives: usize) -> Self {
+        Self(lives)
     }
 }
 
+/// Root of a piece of the in-game HUD.
+#[derive(Component)]
+pub struct HudUi;
+
+// The per player parts of the HUD also carry the `PlayerId` they show
 #[derive(Component)]
 pub struct ScoreUi;
 
 #[derive(Component)]
 pub struct LivesUi;
 
+/// Active power-ups and how long they last.
+#[derive(Component)]
+pub struct PowerUpUi;
+
 #[derive(Component)]
 pub struct MenuUi;
 
-pub fn update_scoreboard(
-    lives: Res<PlayerLives>,
-    score: Res<Score>,
-    lives_root: Single<Entity, (With<LivesUi>, With<Text>)>,
-    score_root: Single<Entity, (With<ScoreUi>, With<Text>)>,
-    mut writer: TextUiWriter,
-) {
-    *writer.text(*lives_root, 1) = lives.to_string();
-    *writer.text(*score_root, 0) = score.to_string();
-}
+/// Goes back to the previous screen, also pressed by Escape.
+#[derive(Component)]
+pub struct BackButton;
 
-#[allow(clippy::type_complexity)]
-pub fn main_menu(
-    mut next_state: ResMut<NextState<GameStates>>,
-    mut text_query: Query<&mut TextFont>,
-    mut interaction_query: Query<(&Interaction, &Children), (Changed<Interaction>, With<Button>)>,
+// Writes the spans directly rather than through `TextUiWriter`, which needs the
+// text plugin and is not available in headless runs.
+pub fn update_scoreboard(
+    ships: Query<(&PlayerId, &PlayerLives, &Score)>,
+    lives_roots: Query<(&PlayerId, &Children), With<LivesUi>>,
+    mut score_roots: Query<(&PlayerId, &mut Text), With<ScoreUi>>,
+    mut spans: Query<&mut TextSpan>,
 ) {
-    for (interaction, children) in &mut interaction_query {
-        for x in children.iter() {
-            if let Ok(mut text) = text_query.get_mut(*x) {
-                match *interaction {
-                    Interaction::Pressed => {
-                        next_state.set(GameStates::Playing);
-                        text.font_size = 18.0;
-                    }
-                    Interaction::Hovered => {
-                        text.font_size = 22.0;
-                    }
-                    Interaction::None => {
-                        text.font_size = 18.0;
-                    }
+    for (id, lives, score) in &ships {
+        for (_, children) in lives_roots.iter().filter(|(i, _)| *i == id) {
+            for child in children.iter() {
+                if let Ok(mut span) = spans.get_mut(*child) {
+                    **span = lives.to_string();
                 }
             }
         }
+        for (_, mut text) in score_roots.iter_mut().filter(|(i, _)| *i == id) {
+            text.0 = score.to_string();
+        }
     }
 }
+
+/// White text for the HUD and the menus.
+pub fn white_text(text: impl Into<String>, font_size: f32) -> (Text, TextFont, TextColor) {
+    (
+        Text::new(text),
+        TextFont {
+            font_size,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    )
+}
+
+/// A white button with a black label, `marker` tells what it does. Insert a
+/// `Node` into the returned button to give it another size.
+pub fn spawn_menu_button<'a>(
+    parent: &'a mut ChildBuilder,
+    label: &str,
+    marker: impl Bundle,
+) -> EntityCommands<'a> {
+    let mut button = parent.spawn((
+        Button,
+        Node {
+            width: Val::Percent(20.),
+            height: Val::Percent(6.),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::WHITE),
+        marker,
+    ));
+    button.with_children(|parent| {
+        parent.spawn((
+            MenuButtonText,
+            Text::new(label),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor::BLACK,
+        ));
+    });
+    button
+}
@@ -0,0 +1,226 @@
+use crate::actions::PlayerInputs;
+use crate::asteroid::Asteroid;
+use crate::bullet::{Bullet, Team};
+use crate::config::GameConfig;
+use crate::lifetime::Lifetime;
+use crate::player::{Hyperspace, Player, PlayerId};
+use crate::saucer::Saucer;
+use crate::transforming::{Physics, Playfield};
+use bevy::color::Color;
+use bevy::math::{EulerRot, Quat, Vec2};
+use bevy::prelude::{
+    Commands, Component, Entity, Or, Query, Res, Sprite, Time, Transform, With, Without, default,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeaponKind {
+    Blaster,
+    SpreadShot,
+    RapidFire,
+    Laser,
+    HomingMissile,
+}
+
+/// How a ship or saucer shoots.
+#[derive(Component, Clone)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    /// Seconds between shots
+    pub cooldown: f32,
+    /// Bullets of one shooter that may be in flight at once
+    pub max_bullets: usize,
+    /// Bullets per shot
+    pub shots: usize,
+    /// Angle in radians between the bullets of one shot
+    pub spread: f32,
+    /// Bullet speed as a multiple of [`GameConfig::bullet_speed`]
+    pub speed: f32,
+    pub lifetime: f32,
+    /// Keeps firing while the button is held
+    pub automatic: bool,
+    /// Radians per second a bullet turns towards its target, 0 flies straight
+    pub homing: f32,
+    pub bullet_size: Vec2,
+    cooldown_left: f32,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind) -> Self {
+        let blaster = Self {
+            kind,
+            cooldown: 0.1,
+            max_bullets: 4,
+            shots: 1,
+            spread: 0.0,
+            speed: 1.0,
+            lifetime: 2.0,
+            automatic: false,
+            homing: 0.0,
+            bullet_size: Vec2::new(4.0, 4.0),
+            cooldown_left: 0.0,
+        };
+        match kind {
+            WeaponKind::Blaster => blaster,
+            WeaponKind::SpreadShot => Self {
+                cooldown: 0.3,
+                max_bullets: 9,
+                shots: 3,
+                spread: 0.25,
+                lifetime: 1.2,
+                ..blaster
+            },
+            WeaponKind::RapidFire => Self {
+                cooldown: 0.08,
+                max_bullets: 12,
+                speed: 1.2,
+                lifetime: 1.0,
+                automatic: true,
+                ..blaster
+            },
+            WeaponKind::Laser => Self {
+                cooldown: 0.03,
+                max_bullets: 30,
+                speed: 1.92,
+                lifetime: 0.4,
+                automatic: true,
+                bullet_size: Vec2::new(10.0, 2.0),
+                ..blaster
+            },
+            WeaponKind::HomingMissile => Self {
+                cooldown: 0.6,
+                max_bullets: 2,
+                speed: 0.64,
+                lifetime: 3.0,
+                homing: 3.0,
+                bullet_size: Vec2::new(6.0, 3.0),
+                ..blaster
+            },
+        }
+    }
+
+    /// A blaster that fires every `cooldown` seconds without a bullet limit,
+    /// starting one cooldown after it appears.
+    pub fn saucer(cooldown: f32) -> Self {
+        Self {
+            cooldown,
+            max_bullets: usize::MAX,
+            speed: 0.8,
+            cooldown_left: cooldown,
+            ..Self::new(WeaponKind::Blaster)
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        self.cooldown_left = (self.cooldown_left - dt).max(0.0);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown_left <= 0.0
+    }
+
+    /// Fires one shot at `angle`, its bullets fanned out around it.
+    pub fn fire(
+        &mut self,
+        commands: &mut Commands,
+        config: &GameConfig,
+        bullet: Bullet,
+        muzzle: Vec2,
+        angle: f32,
+    ) {
+        self.cooldown_left = self.cooldown;
+        let speed = self.speed * config.bullet_speed;
+        let middle = (self.shots as f32 - 1.0) / 2.0;
+        for i in 0..self.shots {
+            let angle = angle + (i as f32 - middle) * self.spread;
+            let mut entity = commands.spawn((
+                Sprite::from_color(Color::WHITE, self.bullet_size),
+                Transform::from_translation(muzzle.extend(0.0))
+                    .with_rotation(Quat::from_rotation_z(angle)),
+                Physics {
+                    vel: Vec2::from_angle(angle) * speed,
+                    max_vel: Vec2::splat(speed),
+                    ..default()
+                },
+                bullet,
+                Lifetime::new(self.lifetime),
+            ));
+            if self.homing > 0.0 {
+                entity.insert(Homing {
+                    turn_rate: self.homing,
+                });
+            }
+        }
+    }
+}
+
+/// Bullet that steers towards the closest asteroid or saucer.
+#[derive(Component)]
+pub struct Homing {
+    turn_rate: f32,
+}
+
+pub fn fire_weapons(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    inputs: Res<PlayerInputs>,
+    time: Res<Time>,
+    mut players: Query<(Entity, &Transform, &mut Weapon, &Hyperspace, &PlayerId), With<Player>>,
+    bullets: Query<&Bullet>,
+) {
+    for (entity, transform, mut weapon, hyperspace, id) in &mut players {
+        let actions = inputs[id.0];
+        weapon.tick(time.delta_secs());
+        if hyperspace.is_jumping() {
+            continue;
+        }
+
+        let trigger = if weapon.automatic {
+            actions.fire_held
+        } else {
+            actions.fire
+        };
+        if !trigger || !weapon.is_ready() {
+            continue;
+        }
+        let live = bullets.iter().filter(|b| b.owner == entity).count();
+        if live >= weapon.max_bullets {
+            continue;
+        }
+
+        let angle = transform.rotation.to_euler(EulerRot::ZXY).0;
+        let muzzle = transform.translation.truncate() + Vec2::from_angle(angle) * 5.0;
+        let bullet = Bullet {
+            owner: entity,
+            team: Team::Players,
+        };
+        weapon.fire(&mut commands, &config, bullet, muzzle, angle);
+    }
+}
+
+type HomingTargets = (Or<(With<Asteroid>, With<Saucer>)>, Without<Homing>);
+
+pub fn steer_homing(
+    time: Res<Time>,
+    playfield: Res<Playfield>,
+    mut missiles: Query<(&mut Transform, &mut Physics, &Homing)>,
+    targets: Query<&Transform, HomingTargets>,
+) {
+    for (mut transform, mut physics, homing) in &mut missiles {
+        let position = transform.translation.truncate();
+        let Some(to_target) = targets
+            .iter()
+            .map(|t| playfield.wrapped_delta(position, t.translation.truncate()))
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        else {
+            continue;
+        };
+
+        let heading = physics.vel.to_angle();
+        let max_turn = homing.turn_rate * time.delta_secs();
+        let turn = Vec2::from_angle(heading)
+            .angle_to(to_target)
+            .clamp(-max_turn, max_turn);
+        physics.vel = Vec2::from_angle(heading + turn) * physics.vel.length();
+        transform.rotation = Quat::from_rotation_z(heading + turn);
+    }
+}
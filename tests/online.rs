@@ -0,0 +1,65 @@
+//! Plays an online game between two headless instances on this machine, over
+//! a link that delays and drops packets, and checks both simulated the same.
+
+use std::net::UdpSocket;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+const TICKS: &str = "600";
+
+fn game() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_bevy-asteroids"));
+    command
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([
+            "--headless",
+            "--ticks",
+            TICKS,
+            "--latency",
+            "30",
+            "--loss",
+            "10",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+fn log(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr)
+}
+
+/// The summary an instance logs when its online game ends.
+fn game_over(log: &str) -> &str {
+    let start = log
+        .find("Online game over")
+        .unwrap_or_else(|| panic!("no end of the online game in:\n{log}"));
+    log[start..].lines().next().unwrap_or_default()
+}
+
+#[test]
+fn host_and_client_stay_in_sync() {
+    let port = UdpSocket::bind("127.0.0.1:0")
+        .and_then(|socket| socket.local_addr())
+        .expect("no free port")
+        .port();
+    let host = game()
+        .args(["--host", &port.to_string(), "--players", "2"])
+        .args(["--seed", "5", "--bot", "1"])
+        .spawn()
+        .expect("failed to start the host");
+    // The client says hello until the host answers, a head start just saves
+    // a few tries
+    thread::sleep(Duration::from_millis(200));
+    let client = game()
+        .args(["--join", &format!("127.0.0.1:{port}"), "--bot", "2"])
+        .output()
+        .expect("failed to start the client");
+    let host = host.wait_with_output().expect("host went missing");
+    let (host, client) = (log(&host), log(&client));
+
+    assert!(!host.contains("out of sync"), "{host}");
+    // The same number of ticks and the same checksums on both sides
+    assert_eq!(game_over(&host), game_over(&client));
+}